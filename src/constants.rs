@@ -3,10 +3,16 @@ use std::collections::HashMap;
 
 pub const KEYBOARD_PERCENTAGE: u16 = 85;
 pub const SEARCH_BOX_PERCENTAGE: u16 = 75;
+pub const PICKER_BOX_PERCENTAGE: u16 = 75;
 pub const TEXT_BOX_PERCENTAGE: u16 = 70;
 pub const TIMER_BOX_PERCENTAGE: u16 = 10;
 pub const SCORE_BOX_PERCENTAGE: u16 = 10;
+pub const QUEUE_ADVANCE_DELAY_SECS: u64 = 5;
+pub const RESULTS_BOX_PERCENTAGE: u16 = 85;
+// Seconds of typing behind every point of the wpm chart.
+pub const WPM_SAMPLE_SECS: f64 = 2.0;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum KeyLength {
     SHORT,
@@ -230,6 +236,12 @@ pub fn initialize_key_coord_map() -> HashMap<KeyCode, Coord> {
         (KeyCode::Char('('), (0, 8)),
         (KeyCode::Char('0'), (0, 9)),
         (KeyCode::Char(')'), (0, 9)),
+        (KeyCode::Char('-'), (0, 10)),
+        (KeyCode::Char('_'), (0, 10)),
+        (KeyCode::Char('='), (0, 11)),
+        (KeyCode::Char('+'), (0, 11)),
+        (KeyCode::Char('\\'), (0, 12)),
+        (KeyCode::Char('|'), (0, 12)),
         // Second row
         (KeyCode::Char('Q'), (1, 0)),
         (KeyCode::Char('W'), (1, 1)),
@@ -258,8 +270,8 @@ pub fn initialize_key_coord_map() -> HashMap<KeyCode, Coord> {
         (KeyCode::Char(';'), (2, 9)),
         (KeyCode::Char(':'), (2, 9)),
         (KeyCode::Char('\''), (2, 10)),
-        (KeyCode::Char('"'), (2, 11)),
-        (KeyCode::Enter, (2, 12)),
+        (KeyCode::Char('"'), (2, 10)),
+        (KeyCode::Enter, (2, 11)),
         // Third row
         (KeyCode::Char('Z'), (3, 0)),
         (KeyCode::Char('X'), (3, 1)),
@@ -279,8 +291,8 @@ pub fn initialize_key_coord_map() -> HashMap<KeyCode, Coord> {
         (KeyCode::Modifier(ModifierKeyCode::LeftSuper), (4, 1)),
         (KeyCode::Modifier(ModifierKeyCode::LeftAlt), (4, 2)),
         (KeyCode::Char(' '), (4, 3)),
-        (KeyCode::Modifier(ModifierKeyCode::RightAlt), (4, 5)),
-        (KeyCode::Modifier(ModifierKeyCode::RightSuper), (4, 6)),
-        (KeyCode::Modifier(ModifierKeyCode::RightControl), (4, 7)),
+        (KeyCode::Modifier(ModifierKeyCode::RightAlt), (4, 4)),
+        (KeyCode::Modifier(ModifierKeyCode::RightSuper), (4, 5)),
+        (KeyCode::Modifier(ModifierKeyCode::RightControl), (4, 6)),
     ])
 }
@@ -5,9 +5,13 @@ use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers};
 use futures::{future::FutureExt, select, StreamExt};
 use futures_timer::Delay;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub enum States {
     EXIT,
+    HELP,
+    LIBRARY,
+    LOOP,
     PAUSE,
     START,
     SEARCH,
@@ -31,7 +35,11 @@ impl KeyboardActions {
     }
 
     pub fn from_char(c: char) -> Self {
-        let k = KeyEvent::from(KeyCode::Char(c));
+        KeyboardActions::from_key_code(KeyCode::Char(c))
+    }
+
+    pub fn from_key_code(key_code: KeyCode) -> Self {
+        let k = KeyEvent::from(key_code);
         KeyboardActions {
             key_event: k,
             state: States::TYPE,
@@ -42,36 +50,44 @@ impl KeyboardActions {
         if *state == States::SEARCH {
             if key_event.eq(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)) {
                 *state = States::SEARCHOFF;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             } else if key_event.eq(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)) {
                 *state = States::SEARCHTERMINATED;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             } else {
                 *state = States::SEARCH;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             }
         } else {
             if key_event.eq(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)) {
                 *state = States::SEARCH;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             } else if key_event.eq(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)) {
-                return KeyboardActions::new(key_event.to_owned(), States::EXIT);
+                KeyboardActions::new(key_event.to_owned(), States::EXIT)
+            } else if key_event.code == KeyCode::F(1) {
+                KeyboardActions::new(key_event.to_owned(), States::HELP)
+            } else if key_event.eq(&KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL)) {
+                KeyboardActions::new(key_event.to_owned(), States::LIBRARY)
+            } else if key_event.eq(&KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)) {
+                KeyboardActions::new(key_event.to_owned(), States::LOOP)
             } else if key_event.eq(&KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL)) {
                 *state = States::PAUSE;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             } else if key_event.eq(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL)) {
                 *state = States::START;
-                return KeyboardActions::new(key_event.to_owned(), *state);
+                KeyboardActions::new(key_event.to_owned(), *state)
             } else {
-                return KeyboardActions::new(key_event.to_owned(), States::TYPE);
+                KeyboardActions::new(key_event.to_owned(), States::TYPE)
             }
         }
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum KeyboardEvent {
     KeyPress(KeyboardActions),
+    // Text pasted into the terminal, it only arrives in one piece with bracketed paste on.
+    Paste(String),
     NoPress,
 }
 
@@ -90,12 +106,17 @@ pub async fn handle_keyboard_events(sn: async_std::channel::Sender<KeyboardEvent
             maybe_event = event => {
                 match maybe_event {
                     Some(Ok(event)) => {
-                        let _ = match event {
+                        match event {
                             Event::Key(k) => {
                                 if let Err(e) = sn.send(KeyboardEvent::KeyPress(KeyboardActions::process_keyevent_for_actions(&k, &mut state))).await {
                                     panic!("Failed to send {}", e.to_string());
                                 }
                             }
+                            Event::Paste(text) => {
+                                if let Err(e) = sn.send(KeyboardEvent::Paste(text)).await {
+                                    panic!("Failed to send {}", e.to_string());
+                                }
+                            }
                             _ => {
                                 if let Err(e) = sn.send(KeyboardEvent::NoPress).await {
                                     panic!("Failed to send {}", e.to_string());
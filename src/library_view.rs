@@ -0,0 +1,93 @@
+use crossterm::event::KeyCode;
+use libreq::difficulty::Difficulty;
+use libreq::library::{Library, LibraryEntry, SortKey};
+
+#[derive(Clone, Debug, Default)]
+pub struct LibraryView {
+    pub sort_key: SortKey,
+    pub selected: usize,
+    pub tag_input: Option<String>,
+}
+
+pub enum LibraryAction {
+    Start(String),
+    Delete(String),
+    ToggleFavorite(String),
+    ToggleTag(String, String),
+    Close,
+    Nothing,
+}
+
+impl LibraryView {
+    pub fn visible<'a>(
+        &self,
+        library: &'a Library,
+        difficulty: Difficulty,
+    ) -> Vec<&'a LibraryEntry> {
+        library.sorted(self.sort_key, difficulty)
+    }
+
+    fn selected_id(&self, library: &Library, difficulty: Difficulty) -> Option<String> {
+        self.visible(library, difficulty)
+            .get(self.selected)
+            .map(|e| e.id.to_owned())
+    }
+
+    pub fn process_key(
+        &mut self,
+        key_code: KeyCode,
+        library: &Library,
+        difficulty: Difficulty,
+    ) -> LibraryAction {
+        if let Some(mut tag) = self.tag_input.take() {
+            match key_code {
+                KeyCode::Char(c) => {
+                    tag.push(c);
+                    self.tag_input = Some(tag);
+                }
+                KeyCode::Backspace => {
+                    tag.pop();
+                    self.tag_input = Some(tag);
+                }
+                KeyCode::Enter => {
+                    if let Some(id) = self.selected_id(library, difficulty) {
+                        return LibraryAction::ToggleTag(id, tag);
+                    }
+                }
+                _ => (),
+            }
+
+            return LibraryAction::Nothing;
+        }
+
+        let len = library.entries.len();
+        match key_code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < len => self.selected += 1,
+            KeyCode::Tab => self.sort_key = self.sort_key.next(),
+            KeyCode::Char('t') => self.tag_input = Some(String::new()),
+            KeyCode::Char('f') => {
+                if let Some(id) = self.selected_id(library, difficulty) {
+                    return LibraryAction::ToggleFavorite(id);
+                }
+            }
+            KeyCode::Delete => {
+                if let Some(id) = self.selected_id(library, difficulty) {
+                    if self.selected + 1 >= len {
+                        self.selected = self.selected.saturating_sub(1);
+                    }
+                    return LibraryAction::Delete(id);
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(id) = self.selected_id(library, difficulty) {
+                    return LibraryAction::Start(id);
+                }
+            }
+            KeyCode::Esc => return LibraryAction::Close,
+            _ => (),
+        }
+
+        LibraryAction::Nothing
+    }
+}
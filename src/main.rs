@@ -1,17 +1,27 @@
 mod constants;
 mod keyboard_event;
+mod library_view;
+mod onboarding;
+mod picker;
+mod race;
 mod renderer;
+mod replay;
 mod state;
 
 use constants::*;
+use onboarding::Onboarding;
+use picker::Picker;
+use race::RaceState;
 use renderer::*;
-use state::TypingState;
-use std::io::{stdout, Result};
+use replay::{forward_quit_keys, replay_keystrokes, start_ghost_race, start_replay};
+use state::{daily_done_message, TypingState};
+use std::io::{stdout, Read, Result};
 
 use core::panic;
 use keyboard_event::handle_keyboard_events;
 use ratatui::{
     crossterm::{
+        event::{DisableBracketedPaste, EnableBracketedPaste},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen},
     },
@@ -19,16 +29,358 @@ use ratatui::{
 };
 
 use libreq::{
+    config::{config_path, CaretStyle, Config},
+    credentials,
+    daily::{daily_path, library_items as daily_items, Daily, DailyLog},
+    difficulty::Difficulty,
+    excerpt::Excerpt,
     generate_client,
-    response::{Root, Song},
-    Lyrics,
+    library::{unix_now, EntryOrigin, Library},
+    lrclib::Lrclib,
+    playlist::{load_playlist, PlaylistItem, Queue, RepeatMode},
+    provider::{Found, LocalFiles, ProviderChain, ProviderName},
+    race::{host_join_addr, join_race, run_server},
+    recording::Recording,
+    response::root_from_text,
+    search::{query_title_artist, Candidate},
+    theme::{ThemeName, ThemeSetting},
+    timed::TimedTest,
+    Lyrics, Musixmatch,
 };
 
+// Asks the configured providers in turn, keeping the quota musixmatch reported for the search box.
+async fn find_lyrics(
+    state_struct: &mut TypingState,
+    musixmatch: &Musixmatch,
+    lrclib: &Lrclib,
+    req: &str,
+    with_cache: bool,
+) -> std::result::Result<Found, String> {
+    let order: Vec<ProviderName> = state_struct
+        .config
+        .providers
+        .0
+        .iter()
+        .copied()
+        .filter(|p| with_cache || *p != ProviderName::Cache)
+        .collect();
+    let chain = ProviderChain {
+        order: &order,
+        offline: state_struct.config.offline,
+        library: &state_struct.library,
+        files: state_struct
+            .config
+            .lyrics_dir
+            .to_owned()
+            .map(|dir| LocalFiles { dir }),
+        musixmatch,
+        lrclib: Some(lrclib),
+    };
+
+    let found = chain.get_lyrics(req.to_owned()).await;
+    state_struct.quota = musixmatch.quota().or(state_struct.quota);
+    found
+}
+
+// Songs of the queue come from the first provider which has them, the library being one of them.
+async fn load_queue_item(
+    state_struct: &mut TypingState,
+    musixmatch: &Musixmatch,
+    lrclib: &Lrclib,
+    item: PlaylistItem,
+) {
+    let loaded = match item {
+        PlaylistItem::File(path) => state_struct
+            .library
+            .import_file(&path)
+            .and_then(|id| state_struct.library.load_root(&id).map(|root| (root, id)))
+            .map(|(root, id)| (root, id, ProviderName::Files))
+            .map_err(|e| format!("Failed to load {}, Error {}", path.display(), e)),
+        PlaylistItem::Search(req) => {
+            match find_lyrics(state_struct, musixmatch, lrclib, &req, true).await {
+                Ok(Found {
+                    root,
+                    provider,
+                    library_id: Some(id),
+                }) => Ok((root, id, provider)),
+                Ok(Found { root, provider, .. }) => {
+                    let (title, artist) = query_title_artist(&req);
+                    state_struct
+                        .library
+                        .add(&title, &artist, None, &root, EntryOrigin::Cache)
+                        .map(|id| (root, id, provider))
+                        .map_err(|e| format!("Failed to save the library {}", e))
+                }
+                Err(e) => Err(e),
+            }
+        }
+    };
+
+    match loaded {
+        Ok((root, id, provider)) => {
+            if let Some(queue) = state_struct.queue.as_mut() {
+                queue.loaded();
+            }
+            state_struct.start_song_from(root, Some(id), Some(provider))
+        }
+        Err(e) => state_struct.skip_queue_item(e),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    // There is no config file until the first run is over.
+    let first_run = !config_path().exists();
+    let mut config = match Config::load(&config_path()) {
+        Ok(c) => c,
+        Err(e) => panic!(
+            "Failed to read the config {}, Error {}",
+            config_path().display(),
+            e
+        ),
+    };
+
+    let mut library = match Library::load(Library::default_dir()) {
+        Ok(l) => l,
+        Err(e) => panic!("Failed to load the song library, Error {}", e),
+    };
+
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|a| a == "--difficulty") {
+        config.difficulty = match args.get(index + 1).and_then(|d| Difficulty::from_name(d)) {
+            Some(d) => d,
+            None => panic!("--difficulty takes one of relaxed, standard or strict"),
+        };
+    }
+
+    if let Some(index) = args.iter().position(|a| a == "--caret") {
+        config.caret = match args.get(index + 1).and_then(|c| CaretStyle::from_name(c)) {
+            Some(c) => c,
+            None => panic!("--caret takes one of block, underline or bar"),
+        };
+    }
+
+    if let Some(index) = args.iter().position(|a| a == "--theme") {
+        config.theme = match args.get(index + 1).and_then(|t| ThemeName::from_name(t)) {
+            Some(t) => ThemeSetting::Bundled(t),
+            None => panic!(
+                "--theme takes one of dark, light, high-contrast, deuteranopia or protanopia"
+            ),
+        };
+    }
+
+    let excerpt = match args.iter().position(|a| a == "--excerpt") {
+        Some(index) => match args.get(index + 1).and_then(|e| Excerpt::from_name(e)) {
+            Some(e) => Some(e),
+            None => panic!("--excerpt takes lines like 12-20, 12- or 12, chorus, seconds like 30s or words like 50w"),
+        },
+        None => None,
+    };
+
+    if args.iter().any(|a| a == "--record") {
+        config.record = true;
+    }
+
+    let replay = match args.iter().position(|a| a == "--replay") {
+        Some(index) => {
+            let path = match args.get(index + 1) {
+                Some(p) => std::path::PathBuf::from(p),
+                None => panic!("--replay needs the path of a recording"),
+            };
+            let speed = match args.iter().position(|a| a == "--speed") {
+                Some(i) => match args.get(i + 1).and_then(|s| s.parse::<f64>().ok()) {
+                    Some(s) if s > 0.0 => s,
+                    _ => panic!("--speed takes a number bigger than 0, like 2 or 0.5"),
+                },
+                None => 1.0,
+            };
+            match Recording::load(&path) {
+                Ok(r) => Some((r, speed)),
+                Err(e) => panic!(
+                    "Failed to read the recording {}, Error {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        None => None,
+    };
+
+    let ghost = match args.iter().position(|a| a == "--ghost") {
+        Some(index) => {
+            let path = match args.get(index + 1) {
+                Some(p) => std::path::PathBuf::from(p),
+                None => panic!("--ghost needs the path of a recording"),
+            };
+            match Recording::load(&path) {
+                Ok(r) => Some(r),
+                Err(e) => panic!(
+                    "Failed to read the recording {}, Error {}",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        None => None,
+    };
+
+    if let Some(index) = args.iter().position(|a| a == "--import") {
+        for path in args.iter().skip(index + 1) {
+            match library.import_file(std::path::Path::new(path)) {
+                Ok(id) => println!("Imported {} as {}", path, id),
+                Err(e) => println!("Failed to import {}, Error {}", path, e),
+            }
+        }
+
+        return Ok(());
+    }
+
+    // A text file, or the text piped into `lyricist -`, is typed right away.
+    let text_song = if let Some(index) = args.iter().position(|a| a == "--file") {
+        let path = match args.get(index + 1) {
+            Some(p) => std::path::PathBuf::from(p),
+            None => panic!("--file needs the path of a text or lyrics file"),
+        };
+        match library
+            .import_file(&path)
+            .and_then(|id| library.load_root(&id).map(|root| (root, Some(id))))
+        {
+            Ok(song) => Some(song),
+            Err(e) => panic!("Failed to load {}, Error {}", path.display(), e),
+        }
+    } else if args.iter().skip(1).any(|a| a == "-") {
+        let mut text = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+            panic!("Failed to read the lyrics from stdin, Error {}", e);
+        }
+        match root_from_text(&text) {
+            root if root.is_empty() => panic!("There are no lines to type on stdin"),
+            root => Some((root, None)),
+        }
+    } else {
+        None
+    };
+
+    let timed = match args.iter().position(|a| a == "--timed") {
+        Some(index) => match args
+            .get(index + 1)
+            .and_then(|t| TimedTest::duration_from_name(t))
+        {
+            Some(seconds) => Some(TimedTest::new(seconds, &library, 1)),
+            None => panic!("--timed takes one of 15, 30, 60 or 120 seconds"),
+        },
+        None => None,
+    };
+    if timed.is_some() && args.iter().any(|a| a == "--playlist" || a == "--daily") {
+        panic!(
+            "--timed streams the songs of the library, it does not go with --playlist or --daily"
+        );
+    }
+
+    let daily = args
+        .iter()
+        .any(|a| a == "--daily")
+        .then(|| Daily::today(unix_now()));
+
+    let queue = match args.iter().position(|a| a == "--playlist") {
+        Some(index) => {
+            let path = match args.get(index + 1) {
+                Some(p) => std::path::PathBuf::from(p),
+                None => panic!("--playlist needs the path of a playlist file"),
+            };
+            let items = match load_playlist(&path) {
+                Ok(items) => items,
+                Err(e) => panic!(
+                    "Failed to read the playlist {}, Error {}",
+                    path.display(),
+                    e
+                ),
+            };
+            let repeat = match args.iter().position(|a| a == "--repeat") {
+                Some(i) => match args.get(i + 1).and_then(|r| RepeatMode::from_name(r)) {
+                    Some(r) => r,
+                    None => panic!("--repeat takes one of off, one or all"),
+                },
+                None => RepeatMode::Off,
+            };
+
+            Some(Queue::new(
+                items,
+                args.iter().any(|a| a == "--shuffle"),
+                repeat,
+            ))
+        }
+        None => None,
+    };
+
+    // The daily song comes from the shared playlist when there is one, in its file order.
+    let queue = match daily {
+        Some(daily) => {
+            let items = match queue {
+                Some(q) => q.items,
+                None => daily_items(&library),
+            };
+            match daily.pick(&items) {
+                Some(item) => Some(Queue::new(vec![item.to_owned()], false, RepeatMode::Off)),
+                None => panic!("--daily needs songs in the library, or a --playlist to pick from"),
+            }
+        }
+        None => queue,
+    };
+
+    // The host runs the server and joins it like everyone else.
+    let race_addr = match args.iter().position(|a| a == "--host") {
+        Some(index) => {
+            let addr = match args.get(index + 1) {
+                Some(a) => a.to_owned(),
+                None => panic!("--host needs an address to listen on, like 0.0.0.0:7878"),
+            };
+            let listener = match tokio::net::TcpListener::bind(&addr).await {
+                Ok(l) => l,
+                Err(e) => panic!("Failed to listen on {}, Error {}", addr, e),
+            };
+            let join_addr = match listener.local_addr() {
+                Ok(a) => host_join_addr(a),
+                Err(e) => panic!("Failed to get the race address, Error {}", e),
+            };
+            tokio::spawn(run_server(listener));
+            Some(join_addr.to_string())
+        }
+        None => match args.iter().position(|a| a == "--join") {
+            Some(index) => match args.get(index + 1) {
+                Some(a) => Some(a.to_owned()),
+                None => panic!("--join needs the address of the host, like 192.168.1.20:7878"),
+            },
+            None => None,
+        },
+    };
+
+    let race = match race_addr {
+        Some(addr) => {
+            let name = match args.iter().position(|a| a == "--name") {
+                Some(index) => args.get(index + 1).cloned(),
+                None => std::env::var("USER").ok(),
+            }
+            .unwrap_or_else(|| String::from("player"));
+
+            match join_race(&addr, &name).await {
+                Ok(channels) => Some(channels),
+                Err(e) => panic!("Failed to join the race at {}, Error {}", addr, e),
+            }
+        }
+        None => None,
+    };
+
+    let credentials = match credentials::resolve(&config) {
+        Ok(c) => c.map(|(c, _)| c),
+        Err(e) => panic!("Failed to read the provider credentials, Error {}", e),
+    };
+
     if let Err(e) = enable_raw_mode() {
         panic!("Failed to enable raw mode Error: {}", e);
     };
+    // Pasted text comes as one event instead of being typed key by key.
+    let _ = execute!(stdout(), EnableBracketedPaste);
 
     let mut terminal: Terminal<CrosstermBackend<_>> =
         match Terminal::new(CrosstermBackend::new(stdout())) {
@@ -36,16 +388,28 @@ async fn main() -> Result<()> {
             Err(e) => panic!("Failed to make a new terminal, Error {}", e),
         };
 
-    let mut state_struct = TypingState {
-        correct_hit: false,
-        correct_hits: 0,
-        error_string: None,
-        keyboard_actions: None,
-        search_request_build: None,
-        search_completed: None,
-        song: None,
-        total_hits: 0,
-        update_text_color: false,
+    let mut state_struct = TypingState::new(config, library);
+    state_struct.queue = queue;
+    state_struct.excerpt = excerpt;
+    state_struct.timed = timed;
+    if let Some(daily) = daily {
+        state_struct.daily = Some(daily);
+        if let Ok(Some(attempt)) =
+            DailyLog::load(&daily_path()).map(|l| l.attempted(daily.day).cloned())
+        {
+            state_struct.status_string = Some(daily_done_message(&attempt));
+        }
+    }
+    if first_run && args.len() == 1 {
+        state_struct.onboarding = Some(Onboarding::new(credentials.is_some()));
+    }
+
+    let (race_tx, mut race_rx) = match race {
+        Some((tx, rx)) => {
+            state_struct.race = Some(RaceState::default());
+            (Some(tx), Some(rx))
+        }
+        None => (None, None),
     };
 
     let (sn, rc) = async_std::channel::unbounded::<keyboard_event::KeyboardEvent>();
@@ -55,37 +419,103 @@ async fn main() -> Result<()> {
     let keys = initialize_key_vec();
     let key_map = initialize_key_coord_map();
 
-    let client = match generate_client() {
+    let client = match generate_client(credentials.as_ref(), &state_struct.config.requests) {
+        Ok(c) => c,
+        Err(e) => panic!("Failed to create the client, check the provider credentials with req auth check. Error -> {}", e),
+    };
+    let provider = Musixmatch::new(client, state_struct.config.provider_url.to_owned())
+        .with_policy(state_struct.config.requests)
+        .with_credentials(credentials);
+    // A client of its own, the RapidAPI headers are not for LRCLIB.
+    let lrclib_client = match generate_client(None, &state_struct.config.requests) {
         Ok(c) => c,
-        Err(e) => panic!("Failed to create the client, also make sure you have the required environemnt variables. Erro -> {}", e),
+        Err(e) => panic!("Failed to create the LRCLIB client, Error -> {}", e),
     };
+    let lrclib = Lrclib::new(lrclib_client, state_struct.config.lrclib_url.to_owned());
 
     let app_layout: AppLayout = generate_app_layout(&mut terminal.get_frame(), &keys);
-    async_std::task::spawn(handle_keyboard_events(sn));
+    if let Some(recording) = ghost {
+        start_ghost_race(&mut state_struct, recording);
+    }
+
+    match replay {
+        Some((recording, speed)) => {
+            start_replay(&mut state_struct, &recording, speed);
+            async_std::task::spawn(replay_keystrokes(sn.clone(), recording.keys, speed));
+
+            // The keyboard only gets to quit, typing would mix into the replayed keystrokes.
+            let (keys_sn, keys_rc) =
+                async_std::channel::unbounded::<keyboard_event::KeyboardEvent>();
+            async_std::task::spawn(forward_quit_keys(keys_rc, sn));
+            async_std::task::spawn(handle_keyboard_events(keys_sn));
+        }
+        None => {
+            async_std::task::spawn(handle_keyboard_events(sn));
+        }
+    }
+
+    state_struct.advance_queue();
+    state_struct.start_timed_test();
+    if let Some((root, id)) = text_song {
+        let provider = id.as_ref().map(|_| ProviderName::Files);
+        state_struct.start_song_from(root, id, provider);
+    }
 
     loop {
+        state_struct.tick();
+
+        if let Some(item) = state_struct.queue_request.take() {
+            load_queue_item(&mut state_struct, &provider, &lrclib, item).await;
+        }
+
         if let Some(req) = state_struct.search_completed.take() {
-            match client.get_lyrics(req.to_owned()).await {
-                Ok(resp) => match resp.json::<Root>().await {
-                    Ok(root) => state_struct.song = Some(Song::new(root)),
-                    Err(e) => {
-                        state_struct.error_string =
-                            Some(format!("Failed to deserialize {}", e.to_string()))
-                    }
-                },
-                Err(e) => state_struct.error_string = Some(format!("Could not get the song you requested, please search a different song, probably some black sabbath: Error ->  {}", e.to_string())),
+            let mut candidates: Vec<Candidate> = Vec::new();
+
+            // The library is searched below, so the chain only asks the other providers.
+            match find_lyrics(&mut state_struct, &provider, &lrclib, &req, false).await {
+                Ok(found) => {
+                    candidates.push(Candidate::from_provider(&req, found.root, found.provider))
+                }
+                Err(e) => state_struct.error_string = Some(e),
             };
+
+            // Songs from the library are offered as well, and still work when the providers fail.
+            candidates.extend(state_struct.library.search(&req));
+            if !candidates.is_empty() {
+                state_struct.error_string = None;
+                state_struct.picker = Some(Picker::new(candidates));
+            }
         }
 
         let quit = match rc.recv().await {
             Ok(rec_eve) => state_struct.process_events_or_exit(rec_eve),
             Err(e) => panic!("Failed to recieve the keyboard event, {}", e.to_string()),
         };
+        state_struct.update_race_progress();
+
+        if let Some(rx) = race_rx.as_mut() {
+            while let Ok(message) = rx.try_recv() {
+                state_struct.process_race_message(message);
+            }
+        }
+
+        if let (Some(tx), Some(race)) = (race_tx.as_ref(), state_struct.race.as_mut()) {
+            for message in race.outbox.drain(..) {
+                let _ = tx.send(message);
+            }
+        }
 
         let _ = terminal.draw(|f| {
-            render_app_layout(f, &app_layout, &keys.clone());
+            render_app_layout(f, &app_layout, &keys.clone(), &state_struct.theme);
             render_events(f, &state_struct, &app_layout, &key_map);
             render_text(f, &state_struct, &app_layout);
+            render_progress(f, &state_struct, &app_layout);
+            render_picker(f, &state_struct, &app_layout);
+            render_race(f, &state_struct, &app_layout);
+            render_results(f, &state_struct, &app_layout);
+            render_library(f, &state_struct, &app_layout);
+            render_onboarding(f, &state_struct, &app_layout);
+            render_help(f, &state_struct, &app_layout);
         });
 
         if quit {
@@ -97,6 +527,7 @@ async fn main() -> Result<()> {
         panic!("Failed to get into alternate Screen {}", e);
     }
 
+    let _ = execute!(stdout(), DisableBracketedPaste);
     if let Err(e) = disable_raw_mode() {
         panic!("Failed to disable raw mode Error: {}", e);
     };
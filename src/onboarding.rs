@@ -0,0 +1,57 @@
+use crossterm::event::KeyCode;
+use libreq::response::{root_from_lines, Root};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnboardingStep {
+    // Checks the provider credentials, and offers the offline mode without them.
+    Provider,
+    Lesson,
+}
+
+#[derive(Clone, Debug)]
+pub struct Onboarding {
+    pub step: OnboardingStep,
+    pub credentials: bool,
+}
+
+pub enum OnboardingAction {
+    UseOffline,
+    StartLesson,
+    Skip,
+    Nothing,
+}
+
+impl Onboarding {
+    pub fn new(credentials: bool) -> Self {
+        Onboarding {
+            step: OnboardingStep::Provider,
+            credentials,
+        }
+    }
+
+    pub fn process_key(&mut self, code: KeyCode) -> OnboardingAction {
+        match (self.step, code) {
+            (_, KeyCode::Esc) => OnboardingAction::Skip,
+            (OnboardingStep::Provider, KeyCode::Char('o')) if !self.credentials => {
+                self.step = OnboardingStep::Lesson;
+                OnboardingAction::UseOffline
+            }
+            (OnboardingStep::Provider, KeyCode::Enter) => {
+                self.step = OnboardingStep::Lesson;
+                OnboardingAction::Nothing
+            }
+            (OnboardingStep::Lesson, KeyCode::Enter) => OnboardingAction::StartLesson,
+            _ => OnboardingAction::Nothing,
+        }
+    }
+}
+
+// A couple of short lines to get used to the keyboard before the first song.
+pub fn sample_lesson() -> Root {
+    root_from_lines(&[
+        "Type the line you see",
+        "Every correct key turns green",
+        "and a wrong one flashes red",
+        "Finish the lesson to see your results",
+    ])
+}
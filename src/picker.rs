@@ -0,0 +1,58 @@
+use crossterm::event::KeyCode;
+use libreq::search::{filter_candidates, Candidate};
+
+#[derive(Clone, Debug)]
+pub struct Picker {
+    pub candidates: Vec<Candidate>,
+    pub filter: String,
+    pub selected: usize,
+}
+
+pub enum PickerAction {
+    Choose(Candidate),
+    Close,
+    Nothing,
+}
+
+impl Picker {
+    pub fn new(candidates: Vec<Candidate>) -> Self {
+        Picker {
+            candidates,
+            filter: String::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn visible(&self) -> Vec<&Candidate> {
+        filter_candidates(&self.candidates, &self.filter)
+            .into_iter()
+            .map(|i| &self.candidates[i])
+            .collect()
+    }
+
+    pub fn process_key(&mut self, key_code: KeyCode) -> PickerAction {
+        let visible_len = self.visible().len();
+
+        match key_code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down if self.selected + 1 < visible_len => self.selected += 1,
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.selected = 0;
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.selected = 0;
+            }
+            KeyCode::Enter => {
+                if let Some(c) = self.visible().get(self.selected) {
+                    return PickerAction::Choose((*c).to_owned());
+                }
+            }
+            KeyCode::Esc => return PickerAction::Close,
+            _ => (),
+        }
+
+        PickerAction::Nothing
+    }
+}
@@ -0,0 +1,17 @@
+use libreq::race::{PlayerProgress, RaceMessage};
+
+#[derive(Clone, Debug, Default)]
+pub struct RaceState {
+    pub player_id: Option<u32>,
+    pub host: bool,
+    pub players: Vec<PlayerProgress>,
+    // Messages waiting for the main loop to send them to the server.
+    pub outbox: Vec<RaceMessage>,
+    pub last_progress: Option<(u32, bool)>,
+}
+
+impl RaceState {
+    pub fn is_over(&self) -> bool {
+        !self.players.is_empty() && self.players.iter().all(|p| p.place.is_some())
+    }
+}
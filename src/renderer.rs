@@ -1,27 +1,44 @@
 use crate::keyboard_event::States;
+use crate::onboarding::OnboardingStep;
 use crate::{constants::*, TypingState};
 use core::f32;
 use crossterm::event::KeyCode;
+use libreq::config::{CaretStyle, LyricAlign};
+use libreq::library::unix_now;
+use libreq::playlist::RepeatMode;
 use libreq::response::SongStatus;
-use ratatui::style::{Color, Style};
+use libreq::theme::Theme;
+use ratatui::style::Style;
 use ratatui::text::{Line, Span, Text};
 use ratatui::{
     layout::{self, Constraint, Direction, Layout, Rect},
     prelude::*,
-    widgets::{Block, BorderType, Borders, Padding, Paragraph},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, BorderType, Borders, Clear, LineGauge, List, ListItem,
+        ListState, Padding, Paragraph, Sparkline, Wrap,
+    },
     Frame,
 };
 use std::collections::HashMap;
-use std::ops::{Div, Mul};
 use std::rc::Rc;
-use std::usize;
 
 pub struct AppLayout {
     text_box: Rect,
     key_layers: Vec<Rc<[Rect]>>,
     total_hits: Rect,
     correct_hits_display: Rect,
+    ghost_display: Rect,
     search_box: Rect,
+    picker_box: Rect,
+    results_box: Rect,
+    progress_box: Rect,
+}
+
+// Borders and titles in the colors of the theme.
+fn themed_block(theme: &Theme) -> Block<'static> {
+    Block::new()
+        .border_style(Style::default().fg(theme.border))
+        .title_style(Style::default().fg(theme.title))
 }
 
 pub fn generate_box(
@@ -44,7 +61,7 @@ pub fn generate_keyboard(
     area: Rect,
     horizontal: Constraint,
     vertical: Constraint,
-    keys: &Vec<Vec<Key>>,
+    keys: &[Vec<Key>],
 ) -> Vec<Rc<[Rect]>> {
     let [area] = Layout::horizontal([horizontal])
         .flex(layout::Flex::Center)
@@ -63,11 +80,10 @@ pub fn generate_keyboard(
     generate_key_layout(Rc::clone(&rects), keys)
 }
 
-pub fn generate_key_layout(key_layers: Rc<[Rect]>, keys: &Vec<Vec<Key>>) -> Vec<Rc<[Rect]>> {
+pub fn generate_key_layout(key_layers: Rc<[Rect]>, keys: &[Vec<Key>]) -> Vec<Rc<[Rect]>> {
     let mut key_layout: Vec<Rc<[Rect]>> = Vec::new();
 
-    let mut current_block: u32 = 0;
-    for key_sub_vec in keys.into_iter() {
+    for (current_block, key_sub_vec) in keys.iter().enumerate() {
         let rat_vec: Vec<(u32, u32)> = key_sub_vec
             .iter()
             .map(|element| match element.key_length {
@@ -79,16 +95,14 @@ pub fn generate_key_layout(key_layers: Rc<[Rect]>, keys: &Vec<Vec<Key>>) -> Vec<
 
         key_layout.push(
             Layout::new(Direction::Horizontal, Constraint::from_ratios(rat_vec))
-                .split(key_layers[current_block as usize]),
+                .split(key_layers[current_block]),
         );
-
-        current_block += 1;
     }
 
     key_layout
 }
 
-pub fn generate_app_layout(frame: &mut Frame, keys: &Vec<Vec<Key>>) -> AppLayout {
+pub fn generate_app_layout(frame: &mut Frame, keys: &[Vec<Key>]) -> AppLayout {
     let key_layers = generate_keyboard(
         frame.size(),
         Constraint::Percentage(KEYBOARD_PERCENTAGE),
@@ -112,6 +126,22 @@ pub fn generate_app_layout(frame: &mut Frame, keys: &Vec<Vec<Key>>) -> AppLayout
         layout::Flex::Center,
     );
 
+    let picker_box = generate_box(
+        frame.size(),
+        Constraint::Percentage(PICKER_BOX_PERCENTAGE),
+        Constraint::Length(frame.size().height / 2),
+        layout::Flex::Start,
+        layout::Flex::Center,
+    );
+
+    let results_box = generate_box(
+        frame.size(),
+        Constraint::Percentage(RESULTS_BOX_PERCENTAGE),
+        Constraint::Length(frame.size().height * 2 / 3),
+        layout::Flex::Start,
+        layout::Flex::Center,
+    );
+
     let correct_hits_display = generate_box(
         frame.size(),
         Constraint::Percentage(TIMER_BOX_PERCENTAGE),
@@ -128,34 +158,58 @@ pub fn generate_app_layout(frame: &mut Frame, keys: &Vec<Vec<Key>>) -> AppLayout
         layout::Flex::End,
     );
 
+    // Right under the lyrics.
+    let progress_box = Rect {
+        y: text_box.bottom(),
+        height: 3,
+        ..text_box
+    }
+    .intersection(frame.size());
+
+    // Right next to the correct hits.
+    let ghost_display = Rect {
+        x: correct_hits_display.right(),
+        ..correct_hits_display
+    }
+    .intersection(frame.size());
+
     AppLayout {
         text_box,
         key_layers,
         search_box,
+        picker_box,
+        results_box,
+        progress_box,
         total_hits,
         correct_hits_display,
+        ghost_display,
     }
 }
 
-pub fn render_app_layout(frame: &mut Frame, key_board_layout: &AppLayout, keys: &[Vec<Key>]) {
+pub fn render_app_layout(
+    frame: &mut Frame,
+    key_board_layout: &AppLayout,
+    keys: &[Vec<Key>],
+    theme: &Theme,
+) {
     frame.render_widget(
-        Block::new().borders(Borders::all()),
+        themed_block(theme).borders(Borders::all()),
         key_board_layout.text_box,
     );
 
     frame.render_widget(
-        Block::new().borders(Borders::ALL),
+        themed_block(theme).borders(Borders::ALL),
         key_board_layout.correct_hits_display,
     );
 
     frame.render_widget(
-        Block::new().borders(Borders::ALL),
+        themed_block(theme).borders(Borders::ALL),
         key_board_layout.total_hits,
     );
 
     for key_layer in key_board_layout.key_layers.iter() {
         for key_rect in key_layer.iter() {
-            frame.render_widget(Block::new().borders(Borders::all()), *key_rect);
+            frame.render_widget(themed_block(theme).borders(Borders::all()), *key_rect);
         }
     }
 
@@ -177,7 +231,7 @@ pub fn render_app_layout(frame: &mut Frame, key_board_layout: &AppLayout, keys:
                 *key_rect,
             );
 
-            key.sec_key_code.map(|sc| {
+            if let Some(sc) = key.sec_key_code {
                 frame.render_widget(
                     Paragraph::new(sc.to_string()).block(Block::new().padding(Padding::new(
                         2,
@@ -187,7 +241,7 @@ pub fn render_app_layout(frame: &mut Frame, key_board_layout: &AppLayout, keys:
                     ))),
                     *key_rect,
                 );
-            });
+            }
         }
     }
 }
@@ -198,32 +252,34 @@ pub fn render_events(
     app_layout: &AppLayout,
     key_map: &HashMap<KeyCode, Coord>,
 ) {
+    let theme = &state_struct.theme;
     if let Some(l_key_event) = state_struct.keyboard_actions {
         match l_key_event.state {
             States::SEARCHTERMINATED => {
                 // Do nothing, this will clear the search box render.
             }
             States::SEARCHOFF => {}
-            States::EXIT => todo!(),
-            States::PAUSE => todo!(),
+            States::EXIT | States::PAUSE | States::START => {}
+            States::HELP | States::LIBRARY | States::LOOP => {}
             States::SEARCH => {
-                match state_struct.search_request_build.clone() {
-                    Some(s) => {
-                        frame.render_widget(
-                            Block::new().borders(Borders::ALL).title("Search"),
-                            app_layout.search_box,
-                        );
-                        frame.render_widget(
-                            Paragraph::new(Text::from(s)).block(Block::new().padding(
-                                Padding::new(5, 5, app_layout.search_box.height / 2 - 1, 0),
-                            )),
-                            app_layout.search_box,
-                        );
-                    }
-                    None => (),
+                if let Some(s) = state_struct.search_request_build.clone() {
+                    frame.render_widget(
+                        themed_block(theme)
+                            .borders(Borders::ALL)
+                            .title(search_title(state_struct)),
+                        app_layout.search_box,
+                    );
+                    frame.render_widget(
+                        Paragraph::new(Text::from(s)).block(Block::new().padding(Padding::new(
+                            5,
+                            5,
+                            app_layout.search_box.height / 2 - 1,
+                            0,
+                        ))),
+                        app_layout.search_box,
+                    );
                 }
             }
-            States::START => todo!(),
             States::TYPE => {
                 if let Some(l_coord) = key_map.get(&l_key_event.key_event.code) {
                     let r = match app_layout.key_layers.get(l_coord.0 as usize) {
@@ -239,7 +295,7 @@ pub fn render_events(
                             Block::bordered()
                                 .border_type(BorderType::QuadrantInside)
                                 .style(Style::new())
-                                .fg(Color::Green),
+                                .fg(theme.key_correct),
                             *r,
                         );
                     } else {
@@ -247,7 +303,7 @@ pub fn render_events(
                             Block::bordered()
                                 .border_type(BorderType::QuadrantInside)
                                 .style(Style::new())
-                                .fg(Color::Red),
+                                .fg(theme.key_incorrect),
                             *r,
                         );
                     }
@@ -256,32 +312,149 @@ pub fn render_events(
                 }
             }
         };
+    } else if let Some(s) = state_struct.search_request_build.clone() {
+        frame.render_widget(
+            themed_block(theme)
+                .borders(Borders::ALL)
+                .title(search_title(state_struct)),
+            app_layout.search_box,
+        );
+        frame.render_widget(
+            Paragraph::new(Text::from(s)).block(Block::new().padding(Padding::new(
+                5,
+                5,
+                app_layout.search_box.height / 2 - 1,
+                0,
+            ))),
+            app_layout.search_box,
+        );
+    }
+}
+
+// The remaining provider quota goes next to the title once a lookup reported it.
+fn search_title(state_struct: &TypingState) -> String {
+    match state_struct.quota {
+        Some(quota) => format!("Search ({})", quota.describe()),
+        None => String::from("Search"),
+    }
+}
+
+// Typed characters in the correct color, or the incorrect one when they were mistyped on the way,
+// the word being typed in bold, the caret on the next character and the ghost cursor highlighted.
+fn sentence_spans(sen: &str, state_struct: &TypingState) -> Vec<Span<'static>> {
+    let theme = &state_struct.theme;
+    let session = match state_struct.session.as_ref() {
+        Some(s) => s,
+        None => return Vec::new(),
+    };
+    let (line_index, index) = (
+        session.song.line_index,
+        session.song.get_current_location() as usize,
+    );
+
+    // The ghost is only drawn while it is on the same line.
+    let ghost_offset = state_struct
+        .get_ghost_progress()
+        .filter(|g| g.line_index == line_index)
+        .map(|g| g.offset as usize);
+
+    let chars: Vec<char> = sen.chars().collect();
+    let word_start = chars[..index.min(chars.len())]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |p| p + 1);
+    let word_end = chars
+        .iter()
+        .skip(index)
+        .position(|c| c.is_whitespace())
+        .map_or(chars.len(), |p| index + p);
+    let caret_color = if session.uncorrected_errors > 0 {
+        theme.incorrect
     } else {
-        match state_struct.search_request_build.clone() {
-            Some(s) => {
-                frame.render_widget(
-                    Block::new().borders(Borders::ALL).title("Search"),
-                    app_layout.search_box,
-                );
-                frame.render_widget(
-                    Paragraph::new(Text::from(s)).block(Block::new().padding(Padding::new(
-                        5,
-                        5,
-                        app_layout.search_box.height / 2 - 1,
-                        0,
-                    ))),
-                    app_layout.search_box,
-                );
+        theme.caret
+    };
+
+    let mut parts: Vec<(String, Style)> = Vec::new();
+    for (i, c) in chars.iter().enumerate() {
+        let mistyped = session.mistakes.contains(&(line_index, i as u32));
+        let mut style = match i < index {
+            true if mistyped => Style::default().fg(theme.incorrect),
+            true => Style::default().fg(theme.correct),
+            false => Style::default().fg(theme.pending),
+        };
+        if (word_start..word_end).contains(&i) {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if ghost_offset == Some(i) {
+            style = style.bg(theme.ghost);
+        }
+
+        if i == index {
+            match state_struct.config.caret {
+                CaretStyle::Block => style = style.fg(theme.selection_fg).bg(caret_color),
+                CaretStyle::Underline => {
+                    style = style.fg(caret_color).add_modifier(Modifier::UNDERLINED)
+                }
+                CaretStyle::Bar => {
+                    parts.push((String::from("\u{258F}"), Style::default().fg(caret_color)))
+                }
             }
-            None => (),
         }
+
+        match parts.last_mut() {
+            Some((part, s)) if *s == style => part.push(*c),
+            _ => parts.push((c.to_string(), style)),
+        }
+    }
+
+    parts
+        .into_iter()
+        .map(|(part, style)| Span::styled(part, style))
+        .collect()
+}
+
+fn queue_status(state_struct: &TypingState) -> String {
+    let queue = match state_struct.queue.as_ref() {
+        Some(q) => q,
+        None => return String::new(),
+    };
+
+    let (position, total) = queue.progress();
+    if !queue.has_next() {
+        format!(
+            ". Playlist finished, {} songs at {:.0} wpm with {:.0}% accuracy",
+            queue.stats.songs_completed,
+            queue.stats.wpm(),
+            queue.stats.accuracy()
+        )
+    } else {
+        format!(
+            ". {} ({}/{}), playlist so far {:.0} wpm with {:.0}% accuracy",
+            match state_struct.advance_at {
+                Some(_) => "Skipping to the next song in a few seconds, n or Enter now, r to stay",
+                None => "n or Enter for the next song",
+            },
+            match queue.repeat {
+                RepeatMode::One => position,
+                _ => position % total + 1,
+            },
+            total,
+            queue.stats.wpm(),
+            queue.stats.accuracy()
+        )
     }
 }
 
 pub fn render_text(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
-    if let Some(err) = state_struct.error_string.as_ref() {
+    let theme = &state_struct.theme;
+    let message = match (&state_struct.error_string, &state_struct.status_string) {
+        (Some(err), _) => Some((err, theme.incorrect)),
+        (None, Some(status)) if state_struct.session.is_none() => Some((status, theme.accent)),
+        _ => None,
+    };
+    if let Some((message, color)) = message {
         frame.render_widget(
-            Paragraph::new(Text::from(err.to_string()).red())
+            Paragraph::new(Text::from(message.to_string()).fg(color))
                 .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
                 .centered(),
             app_layout.text_box,
@@ -294,30 +467,20 @@ pub fn render_text(frame: &mut Frame, state_struct: &TypingState, app_layout: &A
         Some(status) => match status {
             SongStatus::Continuing => match state_struct.get_sentence() {
                 Some(sen) => {
-                    let index: i32 = match state_struct.get_current_cursor_location() {
-                        Some(cur_location) => cur_location.try_into().unwrap(),
-                        None => return,
-                    };
-
-                    if index == 0 {
+                    let paragraph = Paragraph::new(Line::from(sentence_spans(sen, state_struct)));
+                    let top = app_layout.text_box.height / 2;
                     frame.render_widget(
-                    Paragraph::new(sen)
-                        .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
-                        .centered(),
-                    app_layout.text_box,
-                )} else {
-                        let (correct_sen, remaining_sen)= sen.split_at(index as usize);
-                        frame.render_widget(
-                            Paragraph::new(Line::from(vec![
-                                Span::styled(correct_sen,Style::default().fg(Color::Green)),
-                                Span::styled(remaining_sen,Style::default().fg(Color::White)),
-                            ]))
-                                .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
+                        match state_struct.config.align {
+                            LyricAlign::Center => paragraph
+                                .block(Block::new().padding(Padding::top(top)))
                                 .centered(),
-                            app_layout.text_box,
-                        )
-                    }
-                },
+                            LyricAlign::Left => paragraph
+                                .block(Block::new().padding(Padding::new(2, 2, top, 0)))
+                                .left_aligned(),
+                        },
+                        app_layout.text_box,
+                    )
+                }
                 None => frame.render_widget(
                     Paragraph::new("Song completed, search for a new song.")
                         .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
@@ -326,32 +489,622 @@ pub fn render_text(frame: &mut Frame, state_struct: &TypingState, app_layout: &A
                 ),
             },
             SongStatus::Completed => frame.render_widget(
-                Paragraph::new(format!("Song completed, the score is the product of Ratio of correct hits to total hits times 10 = {}", 
-                    (state_struct.correct_hits as f32).div(state_struct.total_hits as f32).mul(10.0 as f32)))
+                Paragraph::new(format!("Song completed, the score is the product of Ratio of correct hits to total hits times 10 = {}{}", 
+                    // No hits at all is a score of 0, not a division by zero.
+                    (state_struct.get_accuracy() / 10.0) as f32,
+                    queue_status(state_struct)))
                     .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
                     .centered(),
                 app_layout.text_box,
             ),
         },
         None => frame.render_widget(
-                    Paragraph::new("Search and start a song, use CTRL-S to search a song, use this format t: <Song title name>, a: <Song artist name>")
+                    Paragraph::new("Search and start a song, use CTRL-S to search a song, use this format t: <Song title name>, a: <Song artist name>, F1 or ? for help")
                         .block(Block::new().padding(Padding::top(app_layout.text_box.height / 2)))
                         .centered(),
                     app_layout.text_box,
         ),
     }
 
+    if let Some(session) = state_struct.session.as_ref() {
+        frame.render_widget(
+            themed_block(theme).title(format!(
+                "Lyrics: {} | Mode: {}{}{}{}",
+                session.song.normalization.describe(),
+                session.difficulty.name(),
+                match state_struct.song_excerpt.as_ref() {
+                    Some(excerpt) => format!(" | Excerpt: {}", excerpt),
+                    None => String::new(),
+                },
+                match session.loop_line {
+                    Some(_) => format!(
+                        " | Looping the line, {} times so far, CTRL-r to go on",
+                        session.loops
+                    ),
+                    None => String::new(),
+                },
+                match session.uncorrected_errors {
+                    0 => String::new(),
+                    n => format!(" | {} errors to fix with Backspace", n),
+                }
+            )),
+            app_layout.text_box,
+        );
+    }
+
     frame.render_widget(
-        Paragraph::new(format!("{}", state_struct.correct_hits))
-            .block(Block::new().title("Correct Hits"))
+        Paragraph::new(format!("{}", state_struct.get_correct_hits()))
+            .block(themed_block(theme).title("Correct Hits"))
             .centered(),
         app_layout.correct_hits_display,
     );
 
+    let ghost = state_struct
+        .get_ghost_progress()
+        .filter(|_| state_struct.get_current_status() == Some(SongStatus::Continuing));
+    if let Some(ghost) = ghost {
+        let lead = state_struct.get_correct_hits() as i64 - ghost.correct_hits as i64;
+        let color = if lead >= 0 {
+            theme.correct
+        } else {
+            theme.incorrect
+        };
+
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                if lead >= 0 {
+                    format!("{} ahead", lead)
+                } else {
+                    format!("{} behind", -lead)
+                },
+                Style::default().fg(color),
+            ))
+            .block(themed_block(theme).borders(Borders::ALL).title("Ghost"))
+            .centered(),
+            app_layout.ghost_display,
+        );
+    }
+
     frame.render_widget(
-        Paragraph::new(format!("{}", state_struct.total_hits))
-            .block(Block::new().title("Total hits"))
+        Paragraph::new(format!("{}", state_struct.get_total_hits()))
+            .block(themed_block(theme).title("Total hits"))
             .centered(),
         app_layout.total_hits,
     );
 }
+
+pub fn render_picker(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    let picker = match state_struct.picker.as_ref() {
+        Some(p) => p,
+        None => return,
+    };
+
+    let items: Vec<ListItem> = picker
+        .visible()
+        .iter()
+        .map(|c| {
+            ListItem::new(format!(
+                "{} - {} | {} | {} | {}",
+                c.title,
+                c.artist,
+                c.album.as_deref().unwrap_or("-"),
+                c.format_duration(),
+                if c.synced { "synced" } else { "plain" }
+            ))
+        })
+        .collect();
+
+    let mut list_state = ListState::default().with_selected(Some(picker.selected));
+
+    frame.render_widget(Clear, app_layout.picker_box);
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(
+                themed_block(theme)
+                    .borders(Borders::ALL)
+                    .title(format!("Results, filter: {}", picker.filter)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(theme.selection_fg)
+                    .bg(theme.selection_bg),
+            )
+            .highlight_symbol("> "),
+        app_layout.picker_box,
+        &mut list_state,
+    );
+}
+
+pub fn render_library(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    let view = match state_struct.library_view.as_ref() {
+        Some(v) => v,
+        None => return,
+    };
+
+    let items: Vec<ListItem> = view
+        .visible(&state_struct.library, state_struct.config.difficulty)
+        .iter()
+        .map(|e| {
+            let last_played = match e.last_played {
+                Some(t) => format!("{}d ago", unix_now().saturating_sub(t) / 86400),
+                None => String::from("never"),
+            };
+
+            ListItem::new(format!(
+                "{} {} - {} | {} | best {} wpm | [{}]",
+                if e.favorite { "*" } else { " " },
+                e.title,
+                e.artist,
+                last_played,
+                e.best_wpm
+                    .get(&state_struct.config.difficulty)
+                    .map_or(String::from("-"), |w| format!("{:.0}", w)),
+                e.tags.join(", ")
+            ))
+        })
+        .collect();
+
+    let title = match view.tag_input.as_ref() {
+        Some(tag) => format!("Library, tag: {}", tag),
+        None => format!(
+            "Library, sorted by {} (Tab sort, f favorite, t tag, Del delete)",
+            view.sort_key.name()
+        ),
+    };
+
+    let mut list_state = ListState::default().with_selected(Some(view.selected));
+
+    frame.render_widget(Clear, app_layout.picker_box);
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(themed_block(theme).borders(Borders::ALL).title(title))
+            .highlight_style(
+                Style::default()
+                    .fg(theme.selection_fg)
+                    .bg(theme.selection_bg),
+            )
+            .highlight_symbol("> "),
+        app_layout.picker_box,
+        &mut list_state,
+    );
+}
+
+pub fn render_results(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    if state_struct.get_current_status() != Some(SongStatus::Completed) {
+        return;
+    }
+
+    let (result, stats) = match (
+        state_struct.last_result.as_ref(),
+        state_struct.session.as_ref(),
+    ) {
+        (Some(r), Some(s)) => (r, s.stats()),
+        _ => return,
+    };
+
+    let area = app_layout.results_box;
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        themed_block(theme)
+            .borders(Borders::ALL)
+            .title(format!("Results: {} - {}", result.title, result.artist))
+            .title_bottom("r retry | n/Enter next song | s save | e export | Esc close"),
+        area,
+    );
+
+    let [summary, chart, details] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(6),
+        Constraint::Min(3),
+    ])
+    .areas(area.inner(Margin::new(1, 1)));
+
+    let best = match state_struct.personal_best.as_ref() {
+        Some(best) => format!("best {:.0} ({:+.0})", best.wpm, result.wpm - best.wpm),
+        None => String::from("first run"),
+    };
+
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(format!(
+                "WPM {:.0}, {} | Accuracy {:.0}% | Consistency {:.0}% | Time {}:{:02} | Mode {}",
+                result.wpm,
+                best,
+                result.accuracy,
+                stats.consistency(WPM_SAMPLE_SECS),
+                (result.seconds as u64) / 60,
+                (result.seconds as u64) % 60,
+                result.difficulty.name()
+            )),
+            Line::from(
+                queue_status(state_struct)
+                    .trim_start_matches(". ")
+                    .to_owned(),
+            ),
+            Line::from(Span::styled(
+                state_struct
+                    .status_string
+                    .as_ref()
+                    .or(state_struct.daily_status.as_ref())
+                    .map_or("", |s| s.as_str()),
+                Style::default().fg(theme.accent),
+            )),
+        ]),
+        summary,
+    );
+
+    let series: Vec<u64> = stats
+        .wpm_series(WPM_SAMPLE_SECS)
+        .iter()
+        .map(|w| w.round() as u64)
+        .collect();
+    frame.render_widget(
+        Sparkline::default()
+            .block(
+                themed_block(theme)
+                    .borders(Borders::TOP)
+                    .title("WPM over time"),
+            )
+            .data(&series)
+            .style(Style::default().fg(theme.correct)),
+        chart,
+    );
+
+    let [words, keys, lines] = Layout::horizontal([
+        Constraint::Percentage(33),
+        Constraint::Percentage(34),
+        Constraint::Percentage(33),
+    ])
+    .areas(details);
+
+    let slowest: Vec<ListItem> = stats
+        .slowest_words(words.height as usize)
+        .iter()
+        .map(|(w, t)| ListItem::new(format!("{} {:.1}s", w, t)))
+        .collect();
+    frame.render_widget(
+        List::new(slowest).block(
+            themed_block(theme)
+                .borders(Borders::TOP)
+                .title("Slowest words"),
+        ),
+        words,
+    );
+
+    let missed: Vec<Bar> = stats
+        .most_missed(8)
+        .iter()
+        .map(|(c, n)| {
+            let label = if c.is_whitespace() {
+                String::from("spc")
+            } else {
+                c.to_string()
+            };
+            Bar::default().value(*n as u64).label(Line::from(label))
+        })
+        .collect();
+    frame.render_widget(
+        BarChart::default()
+            .block(
+                themed_block(theme)
+                    .borders(Borders::TOP)
+                    .title("Most missed keys"),
+            )
+            .data(BarGroup::default().bars(&missed))
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(theme.incorrect)),
+        keys,
+    );
+
+    // In a race the final standings matter more than the line timings.
+    if let Some(race) = state_struct.race.as_ref() {
+        let standings: Vec<ListItem> = race
+            .players
+            .iter()
+            .map(|p| {
+                let place = match p.place {
+                    Some(place) => format!("{}.", place),
+                    None => String::from("-"),
+                };
+                ListItem::new(format!("{} {} {:.0} wpm", place, p.name, p.wpm))
+            })
+            .collect();
+        let title = if race.is_over() {
+            "Standings"
+        } else {
+            "Standings, still racing"
+        };
+        frame.render_widget(
+            List::new(standings).block(themed_block(theme).borders(Borders::TOP).title(title)),
+            lines,
+        );
+        return;
+    }
+
+    let line_times: Vec<ListItem> = stats
+        .line_times
+        .iter()
+        .map(|(l, t)| ListItem::new(format!("line {} {:.1}s", l + 1, t)))
+        .collect();
+    frame.render_widget(
+        List::new(line_times).block(
+            themed_block(theme)
+                .borders(Borders::TOP)
+                .title("Line timings"),
+        ),
+        lines,
+    );
+}
+
+fn format_secs(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// Lines of the song, characters of the line and the time left at the current speed.
+pub fn render_progress(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    let song = match state_struct.session.as_ref() {
+        Some(s) => &s.song,
+        None => return,
+    };
+    if state_struct.get_current_status() != Some(SongStatus::Continuing)
+        || state_struct.picker.is_some()
+        || state_struct.library_view.is_some()
+    {
+        return;
+    }
+
+    let ratio = |(done, total): (u32, u32)| match total {
+        0 => 0.0,
+        total => (done as f64 / total as f64).clamp(0.0, 1.0),
+    };
+    let (lines_done, lines_total) = song.get_line_progress();
+    let (chars_done, chars_total) = song.get_line_char_progress();
+    let eta = match state_struct.get_eta() {
+        Some(secs) => format!("{} left", format_secs(secs)),
+        None => String::from("start typing"),
+    };
+    // A timed test has no end of the song to go by, only the clock.
+    let song_gauge = match (
+        state_struct.session.as_ref().and_then(|s| s.time_left()),
+        state_struct.session.as_ref().and_then(|s| s.time_limit),
+    ) {
+        (Some(left), Some(limit)) => (
+            format!("Time {} left", format_secs(left.ceil())),
+            ratio((((limit - left) * 10.0) as u32, (limit * 10.0) as u32)),
+        ),
+        _ => (
+            format!("Song {:.0}% {}", ratio(song.get_progress()) * 100.0, eta),
+            ratio(song.get_progress()),
+        ),
+    };
+
+    let rows = Layout::vertical([Constraint::Length(1); 3]).split(app_layout.progress_box);
+    let gauges = [
+        (
+            format!("Lines {}/{}", lines_done, lines_total),
+            ratio((lines_done, lines_total)),
+        ),
+        (
+            format!("Line {}/{}", chars_done, chars_total),
+            ratio((chars_done, chars_total)),
+        ),
+        song_gauge,
+    ];
+    for ((label, ratio), row) in gauges.into_iter().zip(rows.iter()) {
+        frame.render_widget(
+            LineGauge::default()
+                .label(label)
+                .filled_style(Style::default().fg(theme.correct))
+                .unfilled_style(Style::default().fg(theme.border))
+                .ratio(ratio),
+            *row,
+        );
+    }
+}
+
+const HELP: [(&str, &str); 25] = [
+    ("CTRL-s", "search a song, t: <title>, a: <artist>"),
+    ("Enter / Esc", "run / cancel the search"),
+    ("CTRL-l", "open or close the library"),
+    (
+        "CTRL-r",
+        "type the current line over and over, again to go on",
+    ),
+    ("F1, ?", "this help, ? only outside of a song"),
+    ("CTRL-p, CTRL-g", "pause and random song, not available yet"),
+    ("Esc", "quit"),
+    ("", ""),
+    (
+        "Song list",
+        "arrows move, type to filter, Enter starts, Esc closes",
+    ),
+    (
+        "Library",
+        "Tab sorts, f favorite, t tag, Delete removes, Enter starts",
+    ),
+    (
+        "Results",
+        "r retry, n/Enter next song, s save, e export, Esc close",
+    ),
+    ("Paste", "outside of a song, type the pasted text"),
+    ("", ""),
+    ("--difficulty", "relaxed, standard or strict"),
+    (
+        "--theme",
+        "dark, light, high-contrast, deuteranopia or protanopia",
+    ),
+    ("--caret", "block, underline or bar"),
+    ("--record", "save the keystrokes of every session"),
+    ("--replay, --ghost", "watch or race a recording"),
+    (
+        "--playlist",
+        "type a list of songs, with --shuffle and --repeat",
+    ),
+    ("--host, --join", "race over the local network"),
+    (
+        "--daily",
+        "the excerpt of the day, with --playlist to share it",
+    ),
+    ("--excerpt", "12-20, chorus, 30s or 50w of every song"),
+    (
+        "--timed",
+        "15, 30, 60 or 120 seconds over the library songs",
+    ),
+    (
+        "--import",
+        "add lyrics json, lrc or text files to the library",
+    ),
+    ("--file, -", "type a file, or the text piped in"),
+];
+
+pub fn render_help(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    if !state_struct.help {
+        return;
+    }
+
+    let area = app_layout.results_box;
+    frame.render_widget(Clear, area);
+
+    let lines: Vec<Line> = HELP
+        .iter()
+        .map(|(keys, action)| {
+            Line::from(vec![
+                Span::styled(format!("{:<20}", keys), Style::default().fg(theme.title)),
+                Span::raw(*action),
+            ])
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            themed_block(theme)
+                .borders(Borders::ALL)
+                .title("Help")
+                .title_bottom(format!(
+                    "Mode {} | Lyrics {} | any key to close",
+                    state_struct.config.difficulty.name(),
+                    state_struct.config.normalize.describe()
+                ))
+                .padding(Padding::uniform(1)),
+        ),
+        area,
+    );
+}
+
+pub fn render_onboarding(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    let onboarding = match state_struct.onboarding.as_ref() {
+        Some(o) => o,
+        None => return,
+    };
+
+    let text = match onboarding.step {
+        OnboardingStep::Provider if onboarding.credentials => vec![
+            Line::from("Welcome to lyricist, type along to the lyrics of your favorite songs."),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Provider credentials found, searches will look the lyrics up online.",
+                Style::default().fg(theme.correct),
+            )),
+            Line::from(""),
+            Line::from("Enter to continue, Esc to skip the introduction."),
+        ],
+        OnboardingStep::Provider => vec![
+            Line::from("Welcome to lyricist, type along to the lyrics of your favorite songs."),
+            Line::from(""),
+            Line::from(Span::styled(
+                "No provider credentials, run req auth set to search online.",
+                Style::default().fg(theme.incorrect),
+            )),
+            Line::from("Without them songs can still be imported with lyricist --import <file>."),
+            Line::from(""),
+            Line::from("o to only use the local library, Enter to continue, Esc to skip."),
+        ],
+        OnboardingStep::Lesson => vec![
+            Line::from("A short lesson comes next, type the line in the middle of the screen."),
+            Line::from("Hit F1 any time, or ? between songs, to see every key."),
+            Line::from(""),
+            Line::from("Enter to start the lesson, Esc to skip it."),
+        ],
+    };
+
+    let area = app_layout.picker_box;
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .block(
+                themed_block(theme)
+                    .borders(Borders::ALL)
+                    .title("Getting started")
+                    .padding(Padding::uniform(1)),
+            )
+            .wrap(Wrap { trim: true }),
+        area,
+    );
+}
+
+// One gauge per player right above the lyrics while racing.
+pub fn render_race(frame: &mut Frame, state_struct: &TypingState, app_layout: &AppLayout) {
+    let theme = &state_struct.theme;
+    let race = match state_struct.race.as_ref() {
+        Some(r) => r,
+        None => return,
+    };
+    if state_struct.get_current_status() != Some(SongStatus::Continuing)
+        || state_struct.picker.is_some()
+        || state_struct.library_view.is_some()
+    {
+        return;
+    }
+
+    let height = (race.players.len() as u16 + 1).min(app_layout.text_box.y);
+    let area = Rect {
+        y: app_layout.text_box.y - height,
+        height,
+        ..app_layout.text_box
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        themed_block(theme)
+            .borders(Borders::TOP)
+            .title(match race.host {
+                true => "Race (host)",
+                false => "Race",
+            }),
+        area,
+    );
+
+    let rows = Layout::vertical(vec![Constraint::Length(1); race.players.len()]).split(Rect {
+        y: area.y + 1,
+        height: area.height.saturating_sub(1),
+        ..area
+    });
+    for (player, row) in race.players.iter().zip(rows.iter()) {
+        let ratio = match player.total {
+            0 => 0.0,
+            total => (player.done as f64 / total as f64).clamp(0.0, 1.0),
+        };
+        let color = if Some(player.id) == race.player_id {
+            theme.correct
+        } else {
+            theme.accent
+        };
+
+        frame.render_widget(
+            LineGauge::default()
+                .label(format!("{} {:.0} wpm", player.name, player.wpm))
+                .filled_style(Style::default().fg(color))
+                .ratio(ratio),
+            *row,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests;
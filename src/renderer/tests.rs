@@ -0,0 +1,172 @@
+use super::*;
+use crate::keyboard_event::{KeyboardActions, KeyboardEvent};
+use libreq::config::Config;
+use libreq::library::Library;
+use libreq::response::root_from_lines;
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use std::path::PathBuf;
+
+const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (160, 50)];
+
+fn state(lines: &[&str]) -> TypingState {
+    let mut state_struct =
+        TypingState::new(Config::default(), Library::empty(std::env::temp_dir()));
+    state_struct.start_song(root_from_lines(lines), None);
+    state_struct
+}
+
+fn type_keys(state_struct: &mut TypingState, typed: &str) {
+    for c in typed.chars() {
+        state_struct.process_events_or_exit(KeyboardEvent::KeyPress(KeyboardActions::from_char(c)));
+    }
+}
+
+fn draw(state_struct: &TypingState, width: u16, height: u16) -> Buffer {
+    let keys = initialize_key_vec();
+    let key_map = initialize_key_coord_map();
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+
+    terminal
+        .draw(|f| {
+            let app_layout = generate_app_layout(f, &keys);
+            render_app_layout(f, &app_layout, &keys, &state_struct.theme);
+            render_events(f, state_struct, &app_layout, &key_map);
+            render_text(f, state_struct, &app_layout);
+        })
+        .unwrap();
+    terminal.backend().buffer().to_owned()
+}
+
+fn buffer_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let line: String = (0..buffer.area.width)
+            .map(|x| buffer.get(x, y).symbol())
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+// Compares with the snapshot in the snapshots folder. Snapshots are only written with
+// UPDATE_SNAPSHOTS=1, a missing one fails like a changed one.
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/renderer/snapshots")
+        .join(format!("{}.txt", name));
+
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok_and(|v| v == "1") {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) => panic!(
+            "No snapshot {} ({}), run with UPDATE_SNAPSHOTS=1 to write it\n--- actual\n{}",
+            path.display(),
+            e,
+            actual
+        ),
+    };
+    if expected != actual {
+        let first = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or(0);
+        panic!(
+            "{} differs from {} from line {}, run with UPDATE_SNAPSHOTS=1 if the change is \
+             intended\n--- expected\n{}\n--- actual\n{}",
+            name,
+            path.display(),
+            first + 1,
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn key_coords_match_the_keyboard() {
+    let keys = initialize_key_vec();
+    let key_map = initialize_key_coord_map();
+
+    for (row, key_row) in keys.iter().enumerate() {
+        for (index, key) in key_row.iter().enumerate() {
+            let expected = (row as u32, index as u32);
+            for code in [Some(key.key_code), key.sec_key_code].into_iter().flatten() {
+                assert_eq!(key_map.get(&code), Some(&expected), "{:?}", code);
+            }
+        }
+    }
+    for (code, (row, index)) in key_map.iter() {
+        assert!(
+            keys.get(*row as usize)
+                .is_some_and(|r| (*index as usize) < r.len()),
+            "{:?} points outside of the keyboard",
+            code
+        );
+    }
+}
+
+#[test]
+fn start_screen() {
+    let state_struct = TypingState::new(Config::default(), Library::empty(std::env::temp_dir()));
+    for (w, h) in SIZES {
+        assert_snapshot(
+            &format!("start_{}x{}", w, h),
+            &buffer_text(&draw(&state_struct, w, h)),
+        );
+    }
+}
+
+#[test]
+fn typing_a_line() {
+    let mut state_struct = state(&["Paranoid", "Finished with my woman"]);
+    type_keys(&mut state_struct, "Pax");
+
+    for (w, h) in SIZES {
+        assert_snapshot(
+            &format!("typing_{}x{}", w, h),
+            &buffer_text(&draw(&state_struct, w, h)),
+        );
+    }
+}
+
+#[test]
+fn every_key_highlights_its_own_rect() {
+    let keys = initialize_key_vec();
+    let mut state_struct = state(&["abc"]);
+    let (w, h) = SIZES[0];
+    let blank = draw(&state_struct, w, h);
+
+    for code in initialize_key_coord_map().keys() {
+        state_struct.keyboard_actions = Some(KeyboardActions::from_key_code(*code));
+        let buffer = draw(&state_struct, w, h);
+
+        let mut terminal = Terminal::new(TestBackend::new(w, h)).unwrap();
+        let mut rect = Rect::default();
+        let _ = terminal.draw(|f| {
+            let app_layout = generate_app_layout(f, &keys);
+            let (row, index) = initialize_key_coord_map()[code];
+            rect = app_layout.key_layers[row as usize][index as usize];
+        });
+
+        // Everything which changed is inside the rect of the key.
+        for y in 0..h {
+            for x in 0..w {
+                if buffer.get(x, y) != blank.get(x, y) {
+                    assert!(
+                        rect.contains(Position { x, y }),
+                        "{:?} drew outside of its key at {},{}",
+                        code,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+    }
+}
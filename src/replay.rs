@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use crate::keyboard_event::{KeyboardActions, KeyboardEvent, States};
+use crate::state::TypingState;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use futures_timer::Delay;
+use libreq::recording::{KeystrokeRecord, RecordedKey, Recording};
+
+pub fn replay_action(key: RecordedKey) -> KeyboardActions {
+    match key {
+        RecordedKey::Char(c) => KeyboardActions::from_char(c),
+        RecordedKey::Backspace => KeyboardActions::from_key_code(KeyCode::Backspace),
+        RecordedKey::Other => KeyboardActions::from_key_code(KeyCode::Null),
+    }
+}
+
+// Puts the state in the same spot the recorded session started from.
+pub fn start_replay(state_struct: &mut TypingState, recording: &Recording, speed: f64) {
+    state_struct.replaying = true;
+    state_struct.time_scale = speed;
+    state_struct.config.difficulty = recording.difficulty;
+    state_struct.config.normalize = recording.normalization.to_owned();
+    state_struct.start_song(recording.lyrics.to_owned(), recording.song_id.to_owned());
+}
+
+// Starts the song of the recording to race against it.
+pub fn start_ghost_race(state_struct: &mut TypingState, recording: Recording) {
+    state_struct.config.difficulty = recording.difficulty;
+    state_struct.config.normalize = recording.normalization.to_owned();
+    let (lyrics, song_id) = (recording.lyrics.to_owned(), recording.song_id.to_owned());
+    state_struct.chosen_ghost = Some(recording);
+    state_struct.start_song(lyrics, song_id);
+}
+
+// Sends the keystrokes with the recorded gaps between them, divided by the speed.
+pub async fn replay_keystrokes(
+    sn: async_std::channel::Sender<KeyboardEvent>,
+    keys: Vec<KeystrokeRecord>,
+    speed: f64,
+) {
+    let mut previous = 0.0;
+    for k in keys {
+        let wait = ((k.at - previous) / speed).max(0.0);
+        Delay::new(Duration::from_secs_f64(wait)).await;
+        previous = k.at;
+
+        if sn
+            .send(KeyboardEvent::KeyPress(replay_action(k.key)))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+// What the keyboard may still do while a replay runs, quit and keep the loop ticking. Anything typed
+// would change the replayed session.
+pub fn replay_passthrough(event: KeyboardEvent) -> Option<KeyboardEvent> {
+    match event {
+        KeyboardEvent::KeyPress(k) if k.state == States::EXIT => Some(KeyboardEvent::KeyPress(k)),
+        KeyboardEvent::KeyPress(k)
+            if k.key_event.code == KeyCode::Char('c')
+                && k.key_event.modifiers == KeyModifiers::CONTROL =>
+        {
+            Some(KeyboardEvent::KeyPress(KeyboardActions {
+                state: States::EXIT,
+                ..k
+            }))
+        }
+        KeyboardEvent::NoPress => Some(KeyboardEvent::NoPress),
+        _ => None,
+    }
+}
+
+// Sits between the keyboard task and the main loop for the length of the program.
+pub async fn forward_quit_keys(
+    rc: async_std::channel::Receiver<KeyboardEvent>,
+    sn: async_std::channel::Sender<KeyboardEvent>,
+) {
+    while let Ok(event) = rc.recv().await {
+        if let Some(event) = replay_passthrough(event) {
+            if sn.send(event).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libreq::config::Config;
+    use libreq::difficulty::Difficulty;
+    use libreq::library::Library;
+    use libreq::normalize::NormalizeOptions;
+    use libreq::response::{root_from_lines, SongStatus};
+
+    fn recording(lines: &[&str], typed: &str, difficulty: Difficulty) -> Recording {
+        let mut recording = Recording::new(
+            None,
+            root_from_lines(lines),
+            difficulty,
+            NormalizeOptions::default(),
+        );
+        recording.keys = typed
+            .chars()
+            .enumerate()
+            .map(|(i, c)| KeystrokeRecord {
+                at: i as f64 * 0.2,
+                key: if c == '\u{8}' {
+                    RecordedKey::Backspace
+                } else {
+                    RecordedKey::Char(c)
+                },
+                expected: None,
+                correct: false,
+                line_index: 0,
+                offset: 0,
+            })
+            .collect();
+        recording
+    }
+
+    fn replay(recording: &Recording) -> TypingState {
+        let mut state_struct =
+            TypingState::new(Config::default(), Library::empty(std::env::temp_dir()));
+        start_replay(&mut state_struct, recording, 1.0);
+
+        for k in recording.keys.iter() {
+            state_struct.process_events_or_exit(KeyboardEvent::KeyPress(replay_action(k.key)));
+        }
+        state_struct
+    }
+
+    #[test]
+    fn only_quitting_gets_through_a_replay() {
+        let passes = |k: KeyboardActions| replay_passthrough(KeyboardEvent::KeyPress(k)).is_some();
+        let key = |code, modifiers, state| KeyboardActions {
+            key_event: crossterm::event::KeyEvent::new(code, modifiers),
+            state,
+        };
+
+        assert!(!passes(KeyboardActions::from_char('a')));
+        assert!(!passes(KeyboardActions::from_key_code(KeyCode::Backspace)));
+        assert!(passes(key(KeyCode::Esc, KeyModifiers::NONE, States::EXIT)));
+        assert!(matches!(
+            replay_passthrough(KeyboardEvent::KeyPress(key(
+                KeyCode::Char('c'),
+                KeyModifiers::CONTROL,
+                States::TYPE
+            ))),
+            Some(KeyboardEvent::KeyPress(k)) if k.state == States::EXIT
+        ));
+        assert!(replay_passthrough(KeyboardEvent::Paste(String::from("x"))).is_none());
+        assert!(replay_passthrough(KeyboardEvent::NoPress).is_some());
+    }
+
+    #[test]
+    fn standard_counts_misses_and_completes() {
+        let state_struct = replay(&recording(&["Hi yo"], "Hi xyo", Difficulty::Standard));
+
+        assert_eq!(state_struct.get_correct_hits(), 5);
+        assert_eq!(state_struct.get_total_hits(), 6);
+        assert_eq!(
+            state_struct.get_current_status(),
+            Some(SongStatus::Completed)
+        );
+        assert_eq!(
+            state_struct
+                .session
+                .as_ref()
+                .unwrap()
+                .stats()
+                .most_missed(1),
+            vec![('y', 1)]
+        );
+    }
+
+    #[test]
+    fn lines_are_typed_one_after_the_other() {
+        let state_struct = replay(&recording(&["ab", "", "cd"], "abc", Difficulty::Standard));
+
+        assert_eq!(state_struct.session.as_ref().unwrap().song.line_index, 2);
+        assert_eq!(state_struct.get_current_char(), Some('d'));
+    }
+
+    #[test]
+    fn strict_needs_mistakes_deleted() {
+        // The b typed after the x does not count, it has to be deleted as well.
+        let state_struct = replay(&recording(&["abc"], "axb\u{8}bc", Difficulty::Strict));
+        assert_eq!(state_struct.get_correct_hits(), 1);
+        assert_eq!(state_struct.session.as_ref().unwrap().uncorrected_errors, 3);
+
+        let state_struct = replay(&recording(&["abc"], "axb\u{8}\u{8}bc", Difficulty::Strict));
+        assert_eq!(state_struct.get_correct_hits(), 3);
+        assert_eq!(
+            state_struct.get_current_status(),
+            Some(SongStatus::Completed)
+        );
+    }
+
+    #[test]
+    fn relaxed_ignores_case_and_punctuation() {
+        let state_struct = replay(&recording(&["Hey, you!"], "hey you", Difficulty::Relaxed));
+
+        assert_eq!(state_struct.get_correct_hits(), 7);
+        assert_eq!(state_struct.get_total_hits(), 7);
+        assert_eq!(
+            state_struct.get_current_status(),
+            Some(SongStatus::Completed)
+        );
+    }
+
+    #[test]
+    fn a_chosen_ghost_stays_for_retries() {
+        let ghost = recording(&["Hi yo"], "Hi yo", Difficulty::Standard);
+        let mut state_struct =
+            TypingState::new(Config::default(), Library::empty(std::env::temp_dir()));
+        start_ghost_race(&mut state_struct, ghost.to_owned());
+
+        for k in ghost.keys.iter() {
+            state_struct.process_events_or_exit(KeyboardEvent::KeyPress(replay_action(k.key)));
+        }
+        state_struct
+            .process_events_or_exit(KeyboardEvent::KeyPress(KeyboardActions::from_char('r')));
+
+        assert_eq!(
+            state_struct.get_current_status(),
+            Some(SongStatus::Continuing)
+        );
+        assert_eq!(state_struct.ghost, Some(ghost));
+    }
+}
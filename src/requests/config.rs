@@ -0,0 +1,115 @@
+use crate::credentials::Credentials;
+use crate::difficulty::Difficulty;
+use crate::normalize::NormalizeOptions;
+use crate::provider::ProviderOrder;
+use crate::retry::RequestPolicy;
+use crate::theme::ThemeSetting;
+use serde_derive::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GhostMode {
+    // Races the recording of the best run on the song, when there is one.
+    #[default]
+    PersonalBest,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaretStyle {
+    // The character under the cursor in reverse colors.
+    #[default]
+    Block,
+    Underline,
+    // A thin line right before the character under the cursor.
+    Bar,
+}
+
+impl CaretStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "block" => Some(CaretStyle::Block),
+            "underline" => Some(CaretStyle::Underline),
+            "bar" => Some(CaretStyle::Bar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricAlign {
+    #[default]
+    Center,
+    // Keeps the start of the line, and so the cursor, from moving between lines.
+    Left,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub difficulty: Difficulty,
+    pub ghost: GhostMode,
+    pub normalize: NormalizeOptions,
+    // Saves a keystroke recording of every session.
+    pub record: bool,
+    pub theme: ThemeSetting,
+    pub caret: CaretStyle,
+    pub align: LyricAlign,
+    // Searches only look in the local library, for when there are no provider credentials.
+    pub offline: bool,
+    // Lyrics endpoint to use instead of the Musixmatch one on RapidAPI.
+    pub provider_url: Option<String>,
+    pub providers: ProviderOrder,
+    // LRCLIB server to use instead of lrclib.net.
+    pub lrclib_url: Option<String>,
+    // Directory of json lyrics files for the `files` provider.
+    pub lyrics_dir: Option<PathBuf>,
+    pub requests: RequestPolicy,
+    // Provider credentials, the secrets file or the environment are safer places for them.
+    pub credentials: Option<Credentials>,
+}
+
+// LYRICIST_CONFIG points straight at the file, otherwise the platform config directory is used.
+pub fn config_path() -> PathBuf {
+    if let Ok(path) = std::env::var("LYRICIST_CONFIG") {
+        return PathBuf::from(path);
+    }
+
+    let dir = if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        PathBuf::from(dir)
+    } else if let Ok(home) = std::env::var("HOME") {
+        PathBuf::from(home).join(".config")
+    } else if let Ok(app_data) = std::env::var("APPDATA") {
+        PathBuf::from(app_data)
+    } else {
+        PathBuf::from(".")
+    };
+
+    dir.join("lyricist").join("config.json")
+}
+
+impl Config {
+    // A missing file is not an error, every option has a default.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let s = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::write(path, s)
+    }
+}
@@ -0,0 +1,247 @@
+use crate::config::{config_path, Config};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+const KEY_VAR: &str = "x_rapid_api_key";
+const HOST_VAR: &str = "x_rapid_api_host";
+
+// The RapidAPI key and host sent with every provider request.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub key: String,
+    pub host: String,
+}
+
+// Where the credentials in use were found, the first one set wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialSource {
+    Env,
+    SecretsFile,
+    Config,
+}
+
+impl fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CredentialSource::Env => "the environment",
+            CredentialSource::SecretsFile => "the secrets file",
+            CredentialSource::Config => "the config file",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// The key never shows up in logs, panics or debug output.
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("key", &self.masked_key())
+            .field("host", &self.host)
+            .finish()
+    }
+}
+
+impl Credentials {
+    pub fn new(key: &str, host: &str) -> Self {
+        Credentials {
+            key: key.trim().to_owned(),
+            host: host.trim().to_owned(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        !self.key.is_empty() && !self.host.is_empty()
+    }
+
+    // Enough of the key to tell two of them apart.
+    pub fn masked_key(&self) -> String {
+        let chars: Vec<char> = self.key.chars().collect();
+        match chars.len() {
+            0..=8 => String::from("****"),
+            n => format!("****{}", chars[n - 4..].iter().collect::<String>()),
+        }
+    }
+
+    // Replaces the key wherever it appears in a message before it is shown.
+    pub fn redact(&self, text: &str) -> String {
+        match self.key.is_empty() {
+            true => text.to_owned(),
+            false => text.replace(&self.key, &self.masked_key()),
+        }
+    }
+
+    // Keys and hosts are plain ASCII, anything else is an error instead of a panic.
+    pub fn headers(&self) -> std::result::Result<HeaderMap, String> {
+        let value = |name: &str, v: &str| match v.chars().all(|c| c.is_ascii_graphic()) {
+            true => HeaderValue::from_str(v).map_err(|e| e.to_string()),
+            false => Err(format!(
+                "The {} can only hold ASCII letters, digits and symbols",
+                name
+            )),
+        };
+
+        let mut key = value("key", &self.key)?;
+        key.set_sensitive(true);
+
+        let mut header = HeaderMap::new();
+        header.insert("x-rapidapi-key", key);
+        header.insert("x-rapidapi-host", value("host", &self.host)?);
+        Ok(header)
+    }
+}
+
+// Next to the config unless LYRICIST_SECRETS says otherwise.
+pub fn secrets_path() -> PathBuf {
+    if let Ok(path) = std::env::var("LYRICIST_SECRETS") {
+        return PathBuf::from(path);
+    }
+
+    config_path().with_file_name("secrets.json")
+}
+
+// The secrets file is refused when anyone but its owner can read it.
+pub fn load_secrets(path: &Path) -> Result<Option<Credentials>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "{} can be read by other users, run chmod 600 on it",
+                    path.display()
+                ),
+            ));
+        }
+    }
+
+    serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map(Some)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+pub fn save_secrets(path: &Path, credentials: &Credentials) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let s = serde_json::to_string_pretty(credentials)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    std::io::Write::write_all(&mut options.open(path)?, s.as_bytes())?;
+
+    // The mode only applies to new files, an older one is tightened as well.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+// The credentials from the environment, the secrets file or the config, in that order.
+pub fn resolve(config: &Config) -> Result<Option<(Credentials, CredentialSource)>> {
+    resolve_from(|name| std::env::var(name).ok(), &secrets_path(), config)
+}
+
+pub fn resolve_from(
+    env: impl Fn(&str) -> Option<String>,
+    secrets: &Path,
+    config: &Config,
+) -> Result<Option<(Credentials, CredentialSource)>> {
+    if let (Some(key), Some(host)) = (env(KEY_VAR), env(HOST_VAR)) {
+        let credentials = Credentials::new(&key, &host);
+        if credentials.is_complete() {
+            return Ok(Some((credentials, CredentialSource::Env)));
+        }
+    }
+
+    if let Some(credentials) = load_secrets(secrets)?.filter(Credentials::is_complete) {
+        return Ok(Some((credentials, CredentialSource::SecretsFile)));
+    }
+
+    Ok(config
+        .credentials
+        .clone()
+        .filter(Credentials::is_complete)
+        .map(|c| (c, CredentialSource::Config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn sources_are_tried_in_order() {
+        let dir = std::env::temp_dir().join(format!("lyricist-secrets-{}", std::process::id()));
+        let secrets = dir.join("secrets.json");
+        let config = Config {
+            credentials: Some(Credentials::new("config-key", "host")),
+            ..Default::default()
+        };
+
+        let found = resolve_from(env(&[]), &secrets, &config).unwrap().unwrap();
+        assert_eq!(found.1, CredentialSource::Config);
+
+        save_secrets(&secrets, &Credentials::new("file-key", "host")).unwrap();
+        let found = resolve_from(env(&[]), &secrets, &config).unwrap().unwrap();
+        assert_eq!(
+            (found.0.key.as_str(), found.1),
+            ("file-key", CredentialSource::SecretsFile)
+        );
+
+        let vars = &[(KEY_VAR, "env-key"), (HOST_VAR, "host")];
+        let found = resolve_from(env(vars), &secrets, &config).unwrap().unwrap();
+        assert_eq!(
+            (found.0.key.as_str(), found.1),
+            ("env-key", CredentialSource::Env)
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&secrets, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let e = resolve_from(env(&[]), &secrets, &config).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keys_are_redacted() {
+        let credentials = Credentials::new("0123456789abcdef", "example.p.rapidapi.com");
+        assert_eq!(credentials.masked_key(), "****cdef");
+        assert_eq!(
+            credentials.redact("key 0123456789abcdef refused"),
+            "key ****cdef refused"
+        );
+        assert!(!format!("{:?}", credentials).contains("0123456789"));
+        assert!(Credentials::new("ké\n", "host").headers().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::library::{data_dir, Library};
+use crate::playlist::PlaylistItem;
+use crate::response::Root;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+const DAY: u64 = 86_400;
+// Lines with some text in the excerpt of the day.
+pub const EXCERPT_LINES: usize = 4;
+
+// splitmix64, the same day gives the same number on every machine.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// The challenge of one UTC day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Daily {
+    // Days since the epoch.
+    pub day: u64,
+    pub seed: u64,
+}
+
+impl Daily {
+    pub fn for_day(day: u64) -> Self {
+        Daily {
+            day,
+            seed: mix(day),
+        }
+    }
+
+    pub fn today(now: u64) -> Self {
+        Daily::for_day(now / DAY)
+    }
+
+    // The song of the day out of the items, which have to be in the same order for everyone.
+    pub fn pick<'a, T>(&self, items: &'a [T]) -> Option<&'a T> {
+        match items.len() {
+            0 => None,
+            n => items.get((self.seed % n as u64) as usize),
+        }
+    }
+
+    // A run of lines of the song, starting at the same line for everyone. It is cut from the lyrics
+    // each player got, which only match when they come from the same place, a lyrics file of the
+    // shared playlist or the same provider.
+    pub fn excerpt(&self, root: &Root) -> Root {
+        let lines: Vec<usize> = (0..root.len())
+            .filter(|i| !root[*i].text.trim().is_empty())
+            .collect();
+        if lines.len() <= EXCERPT_LINES {
+            return root.to_owned();
+        }
+
+        let start = (mix(self.seed) % (lines.len() - EXCERPT_LINES + 1) as u64) as usize;
+        lines[start..start + EXCERPT_LINES]
+            .iter()
+            .map(|i| root[*i].to_owned())
+            .collect()
+    }
+}
+
+// The songs a daily is picked from without a shared playlist, the library sorted by id so the same
+// library always gives the same song.
+pub fn library_items(library: &Library) -> Vec<PlaylistItem> {
+    let mut entries: Vec<_> = library.entries.iter().collect();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    entries
+        .iter()
+        .map(|e| PlaylistItem::Search(format!("t: {}, a: {}", e.title, e.artist)))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyScore {
+    pub title: String,
+    pub artist: String,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub score: u64,
+    // An attempt is saved when it starts, quitting before the end leaves it unfinished.
+    #[serde(default = "finished_default")]
+    pub finished: bool,
+}
+
+// Scores saved before unfinished attempts were kept are all finished ones.
+fn finished_default() -> bool {
+    true
+}
+
+impl DailyScore {
+    // Speed weighted by accuracy, so mashing keys does not pay off.
+    pub fn score(wpm: f64, accuracy: f64) -> u64 {
+        (wpm * (accuracy / 100.0).powi(2) * 10.0).round().max(0.0) as u64
+    }
+}
+
+// The first attempt of every day, which is the one that counts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyLog {
+    pub days: BTreeMap<u64, DailyScore>,
+}
+
+pub fn daily_path() -> PathBuf {
+    data_dir().join("daily.json")
+}
+
+impl DailyLog {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(DailyLog::default());
+        }
+
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let s = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::write(path, s)
+    }
+
+    pub fn attempted(&self, day: u64) -> Option<&DailyScore> {
+        self.days.get(&day)
+    }
+
+    // Uses up the day as the excerpt starts, false when it already has its attempt.
+    pub fn start(&mut self, day: u64, title: &str, artist: &str) -> bool {
+        if self.days.contains_key(&day) {
+            return false;
+        }
+
+        self.days.insert(
+            day,
+            DailyScore {
+                title: title.to_owned(),
+                artist: artist.to_owned(),
+                wpm: 0.0,
+                accuracy: 0.0,
+                score: 0,
+                finished: false,
+            },
+        );
+        true
+    }
+
+    // Scores the attempt started on the day, false when there is none waiting for its score.
+    pub fn finish(&mut self, day: u64, wpm: f64, accuracy: f64) -> bool {
+        match self.days.get_mut(&day) {
+            Some(attempt) if !attempt.finished => {
+                attempt.wpm = wpm;
+                attempt.accuracy = accuracy;
+                attempt.score = DailyScore::score(wpm, accuracy);
+                attempt.finished = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Days in a row with an attempt, up to today. A streak is not broken before today is over.
+    pub fn streak(&self, today: u64) -> u32 {
+        let mut day = match self.days.contains_key(&today) {
+            true => today,
+            false => today.saturating_sub(1),
+        };
+
+        let mut streak = 0;
+        while self.days.contains_key(&day) {
+            streak += 1;
+            match day.checked_sub(1) {
+                Some(d) => day = d,
+                None => break,
+            }
+        }
+        streak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::root_from_lines;
+
+    #[test]
+    fn the_same_day_picks_the_same_excerpt() {
+        let song = root_from_lines(&["a", "", "b", "c", "d", "", "e", "f", "g"]);
+        let items = ["one", "two", "three"];
+
+        let today = Daily::for_day(20_745);
+        assert_eq!(today, Daily::today(20_745 * DAY + 3600));
+        assert_eq!(today.pick(&items), Daily::for_day(20_745).pick(&items));
+        assert_eq!(today.excerpt(&song), Daily::for_day(20_745).excerpt(&song));
+
+        let excerpt = today.excerpt(&song);
+        assert_eq!(excerpt.len(), EXCERPT_LINES);
+        assert!(excerpt.iter().all(|l| !l.text.is_empty()));
+
+        assert_eq!(today.pick::<&str>(&[]), None);
+        assert_eq!(
+            today
+                .excerpt(&root_from_lines(&["short", "", "song"]))
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn only_the_first_attempt_counts_and_streaks_add_up() {
+        let mut log = DailyLog::default();
+        assert!(log.start(10, "Paranoid", "Black Sabbath"));
+        assert!(log.finish(10, 50.0, 100.0));
+        assert!(log.start(11, "Paranoid", "Black Sabbath"));
+        assert!(log.finish(11, 60.0, 100.0));
+        assert!(!log.start(11, "Paranoid", "Black Sabbath"));
+        assert!(!log.finish(11, 90.0, 100.0));
+        assert_eq!(log.attempted(11).unwrap().score, 600);
+
+        // Leaving halfway still uses up the day.
+        assert!(log.start(12, "Paranoid", "Black Sabbath"));
+        assert!(!log.attempted(12).unwrap().finished);
+        assert!(!log.start(12, "Paranoid", "Black Sabbath"));
+
+        assert_eq!(log.streak(11), 2);
+        assert_eq!(log.streak(12), 3);
+        assert_eq!(log.streak(13), 3);
+        assert_eq!(log.streak(14), 0);
+    }
+}
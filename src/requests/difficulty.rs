@@ -0,0 +1,49 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    // Case does not matter and punctuation is skipped over.
+    Relaxed,
+    #[default]
+    Standard,
+    // Exact match, and a mistake has to be deleted with backspace before going on.
+    Strict,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "relaxed" => Some(Difficulty::Relaxed),
+            "standard" => Some(Difficulty::Standard),
+            "strict" => Some(Difficulty::Strict),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Relaxed => "relaxed",
+            Difficulty::Standard => "standard",
+            Difficulty::Strict => "strict",
+        }
+    }
+
+    pub fn matches(self, expected: char, typed: char) -> bool {
+        match self {
+            Difficulty::Relaxed => expected.to_lowercase().eq(typed.to_lowercase()),
+            Difficulty::Standard | Difficulty::Strict => expected == typed,
+        }
+    }
+
+    // Characters the user never has to type in this mode.
+    pub fn skips(self, expected: char) -> bool {
+        self == Difficulty::Relaxed && expected.is_ascii_punctuation()
+    }
+
+    pub fn must_correct(self) -> bool {
+        self == Difficulty::Strict
+    }
+}
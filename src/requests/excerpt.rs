@@ -0,0 +1,246 @@
+use crate::response::{Root, Root2};
+
+// Speed the seconds of an excerpt are counted at when there is no history to go by.
+pub const DEFAULT_WPM: f64 = 40.0;
+
+// The part of a song to practice instead of all of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Excerpt {
+    // Lines counted from 1 without the blank ones, to the end of the song without an end.
+    Lines { start: usize, end: Option<usize> },
+    // The longest block of lines sung more than once.
+    Chorus,
+    // About as many words as can be typed in the seconds.
+    Seconds(u32),
+    Words(u32),
+}
+
+impl Excerpt {
+    // `12-20`, `12-`, `12`, `chorus`, `30s` or `50w`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        if name == "chorus" {
+            return Some(Excerpt::Chorus);
+        }
+        if let Some(secs) = name.strip_suffix('s') {
+            return secs.parse().ok().filter(|s| *s > 0).map(Excerpt::Seconds);
+        }
+        if let Some(words) = name.strip_suffix('w') {
+            return words.parse().ok().filter(|w| *w > 0).map(Excerpt::Words);
+        }
+
+        let (start, end) = match name.split_once('-') {
+            Some((start, "")) => (start.parse().ok()?, None),
+            Some((start, end)) => (start.parse().ok()?, Some(end.parse().ok()?)),
+            None => (name.parse().ok()?, name.parse().ok()),
+        };
+        match (start, end) {
+            (0, _) => None,
+            (start, Some(end)) if end < start => None,
+            (start, end) => Some(Excerpt::Lines { start, end }),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Excerpt::Lines { start, end: None } => format!("lines {}-", start),
+            Excerpt::Lines {
+                start,
+                end: Some(end),
+            } if start == end => format!("line {}", start),
+            Excerpt::Lines {
+                start,
+                end: Some(end),
+            } => format!("lines {}-{}", start, end),
+            Excerpt::Chorus => String::from("chorus"),
+            Excerpt::Seconds(secs) => format!("{} seconds", secs),
+            Excerpt::Words(words) => format!("{} words", words),
+        }
+    }
+
+    // The lines of the excerpt, `wpm` is the speed the seconds are turned into words with.
+    pub fn cut(&self, root: &Root, wpm: f64) -> Result<Root, String> {
+        let lines: Vec<&Root2> = root.iter().filter(|l| !l.text.trim().is_empty()).collect();
+
+        let excerpt = match *self {
+            Excerpt::Lines { start, end } => {
+                if start > lines.len() {
+                    return Err(format!(
+                        "The song has only {} lines, {} is past the end.",
+                        lines.len(),
+                        self.describe()
+                    ));
+                }
+                let end = end.unwrap_or(lines.len()).min(lines.len());
+                lines[start - 1..end].to_vec()
+            }
+            Excerpt::Chorus => match find_chorus(&lines) {
+                Some((start, len)) => lines[start..start + len].to_vec(),
+                None => {
+                    return Err(String::from(
+                        "The song has no repeated block of lines to use as the chorus.",
+                    ))
+                }
+            },
+            Excerpt::Seconds(secs) => {
+                return Ok(take_words(
+                    &lines,
+                    (secs as f64 / 60.0 * wpm).ceil() as usize,
+                ))
+            }
+            Excerpt::Words(words) => return Ok(take_words(&lines, words as usize)),
+        };
+
+        Ok(excerpt.into_iter().cloned().collect())
+    }
+}
+
+// Lines compared without case and punctuation, a chorus is often written a bit differently.
+fn comparable(line: &Root2) -> String {
+    line.text
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Start and length of the longest run of at least two lines which comes back later in the song,
+// the first time it is sung.
+pub fn find_chorus(lines: &[&Root2]) -> Option<(usize, usize)> {
+    let lines: Vec<String> = lines.iter().map(|l| comparable(l)).collect();
+    let mut best: Option<(usize, usize)> = None;
+
+    for i in 0..lines.len() {
+        for j in i + 1..lines.len() {
+            let mut len = 0;
+            while j + len < lines.len() && i + len < j && lines[i + len] == lines[j + len] {
+                len += 1;
+            }
+
+            if len >= 2 && best.is_none_or(|(_, l)| len > l) {
+                best = Some((i, len));
+            }
+        }
+    }
+
+    best
+}
+
+// Whole lines from the start until the words run out, the last one cut after its last word.
+fn take_words(lines: &[&Root2], words: usize) -> Root {
+    let mut left = words.max(1);
+    let mut root = Root::new();
+
+    for line in lines {
+        let count = line.text.split_whitespace().count();
+        if count < left {
+            root.push((*line).to_owned());
+            left -= count;
+            continue;
+        }
+
+        root.push(Root2 {
+            text: line
+                .text
+                .split_whitespace()
+                .take(left)
+                .collect::<Vec<_>>()
+                .join(" "),
+            time: line.time.to_owned(),
+        });
+        break;
+    }
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::root_from_lines;
+
+    fn texts(root: &Root) -> Vec<&str> {
+        root.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    const SONG: [&str; 9] = [
+        "Verse one",
+        "",
+        "Oh la la",
+        "Sing it now",
+        "Verse two goes here",
+        "",
+        "oh, la la!",
+        "Sing it now",
+        "The end",
+    ];
+
+    #[test]
+    fn excerpts_are_parsed() {
+        assert_eq!(
+            Excerpt::from_name("12-20"),
+            Some(Excerpt::Lines {
+                start: 12,
+                end: Some(20)
+            })
+        );
+        assert_eq!(
+            Excerpt::from_name("3-"),
+            Some(Excerpt::Lines {
+                start: 3,
+                end: None
+            })
+        );
+        assert_eq!(Excerpt::from_name("4").unwrap().describe(), "line 4");
+        assert_eq!(Excerpt::from_name("Chorus"), Some(Excerpt::Chorus));
+        assert_eq!(Excerpt::from_name("30s"), Some(Excerpt::Seconds(30)));
+        assert_eq!(Excerpt::from_name("50w"), Some(Excerpt::Words(50)));
+        for bad in ["0-2", "5-3", "0s", "verse", "-4"] {
+            assert_eq!(Excerpt::from_name(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn lines_skip_blanks_and_stop_at_the_end() {
+        let song = root_from_lines(&SONG);
+        let cut = Excerpt::from_name("2-3").unwrap().cut(&song, DEFAULT_WPM);
+        assert_eq!(texts(&cut.unwrap()), vec!["Oh la la", "Sing it now"]);
+
+        let cut = Excerpt::from_name("6-40").unwrap().cut(&song, DEFAULT_WPM);
+        assert_eq!(texts(&cut.unwrap()), vec!["Sing it now", "The end"]);
+
+        let e = Excerpt::from_name("8").unwrap().cut(&song, DEFAULT_WPM);
+        assert_eq!(
+            e.unwrap_err(),
+            "The song has only 7 lines, line 8 is past the end."
+        );
+    }
+
+    #[test]
+    fn the_chorus_is_the_longest_repeated_block() {
+        let cut = Excerpt::Chorus
+            .cut(&root_from_lines(&SONG), DEFAULT_WPM)
+            .unwrap();
+        assert_eq!(texts(&cut), vec!["Oh la la", "Sing it now"]);
+
+        let e = Excerpt::Chorus.cut(&root_from_lines(&["One", "Two", "One"]), DEFAULT_WPM);
+        assert!(e.is_err());
+    }
+
+    #[test]
+    fn fixed_lengths_count_words() {
+        let song = root_from_lines(&SONG);
+        let cut = Excerpt::Words(5).cut(&song, DEFAULT_WPM).unwrap();
+        assert_eq!(texts(&cut), vec!["Verse one", "Oh la la"]);
+
+        let cut = Excerpt::Words(6).cut(&song, DEFAULT_WPM).unwrap();
+        assert_eq!(texts(&cut), vec!["Verse one", "Oh la la", "Sing"]);
+
+        // Six seconds at 40 wpm is four words.
+        let cut = Excerpt::Seconds(6).cut(&song, DEFAULT_WPM).unwrap();
+        assert_eq!(texts(&cut), vec!["Verse one", "Oh la"]);
+    }
+}
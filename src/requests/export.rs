@@ -0,0 +1,429 @@
+use crate::difficulty::Difficulty;
+use crate::history::SessionResult;
+use crate::library::data_dir;
+use crate::stats::KeyStats;
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+const DAY: u64 = 86_400;
+
+pub fn exports_dir() -> PathBuf {
+    data_dir().join("exports")
+}
+
+// Days since the epoch of a civil date, after Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+
+    (yoe + era * 400 + (month <= 2) as i64, month, day)
+}
+
+// The start of a `YYYY-MM-DD` day in UTC, in seconds since the epoch.
+pub fn parse_date(s: &str) -> Option<u64> {
+    let mut parts = s.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    u64::try_from(days_from_civil(year, month, day) * DAY as i64).ok()
+}
+
+pub fn format_date(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / DAY) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Sessions finished from the start of `from` to the end of `to`, both optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Option<u64>,
+    // Exclusive, the start of the day after the last one.
+    pub until: Option<u64>,
+}
+
+impl DateRange {
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> std::result::Result<Self, String> {
+        let date = |s: &str| parse_date(s).ok_or_else(|| format!("{} is not a YYYY-MM-DD date", s));
+
+        Ok(DateRange {
+            from: from.map(date).transpose()?,
+            until: to.map(date).transpose()?.map(|d| d + DAY),
+        })
+    }
+
+    // The last `days` days, today included.
+    pub fn last_days(days: u64, now: u64) -> Self {
+        let tomorrow = (now / DAY + 1) * DAY;
+        DateRange {
+            from: Some(tomorrow.saturating_sub(days * DAY)),
+            until: Some(tomorrow),
+        }
+    }
+
+    pub fn contains(&self, at: u64) -> bool {
+        self.from.is_none_or(|f| at >= f) && self.until.is_none_or(|u| at < u)
+    }
+
+    pub fn describe(&self) -> String {
+        let last = |u: u64| format_date(u.saturating_sub(DAY));
+        match (self.from, self.until) {
+            (Some(f), Some(u)) => format!("{} to {}", format_date(f), last(u)),
+            (Some(f), None) => format!("Since {}", format_date(f)),
+            (None, Some(u)) => format!("Until {}", last(u)),
+            (None, None) => String::from("All sessions"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        ExportFormat::from_name(path.extension()?.to_str()?)
+    }
+}
+
+pub fn in_range<'a>(results: &'a [SessionResult], range: &DateRange) -> Vec<&'a SessionResult> {
+    results
+        .iter()
+        .filter(|r| range.contains(r.finished_at))
+        .collect()
+}
+
+pub fn key_totals(results: &[&SessionResult]) -> BTreeMap<char, KeyStats> {
+    let mut keys: BTreeMap<char, KeyStats> = BTreeMap::new();
+    for (key, stats) in results.iter().flat_map(|r| r.keys.iter()) {
+        keys.entry(*key).or_default().add(*stats);
+    }
+    keys
+}
+
+fn key_name(key: char) -> String {
+    match key {
+        ' ' => String::from("space"),
+        c => c.to_string(),
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    match s.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_owned(),
+    }
+}
+
+pub fn sessions_csv(results: &[&SessionResult]) -> String {
+    let mut csv = String::from(
+        "finished_at,date,title,artist,difficulty,wpm,accuracy,correct_hits,total_hits,seconds,provider,excerpt\n",
+    );
+
+    for r in results {
+        let fields = [
+            r.finished_at.to_string(),
+            format_date(r.finished_at),
+            csv_field(&r.title),
+            csv_field(&r.artist),
+            r.difficulty.name().to_owned(),
+            format!("{:.1}", r.wpm),
+            format!("{:.1}", r.accuracy),
+            r.correct_hits.to_string(),
+            r.total_hits.to_string(),
+            format!("{:.1}", r.seconds),
+            r.provider.map_or("", |p| p.name()).to_owned(),
+            csv_field(r.excerpt.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn keys_csv(results: &[&SessionResult]) -> String {
+    let mut csv = String::from("key,hits,misses,accuracy\n");
+    for (key, stats) in key_totals(results) {
+        csv.push_str(&format!(
+            "{},{},{},{:.1}\n",
+            csv_field(&key_name(key)),
+            stats.hits,
+            stats.misses,
+            stats.accuracy()
+        ));
+    }
+    csv
+}
+
+#[derive(Serialize)]
+struct KeyRow {
+    key: char,
+    hits: u32,
+    misses: u32,
+    accuracy: f64,
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    sessions: &'a [&'a SessionResult],
+    keys: Vec<KeyRow>,
+}
+
+pub fn to_json(results: &[&SessionResult]) -> Result<String> {
+    let keys = key_totals(results)
+        .into_iter()
+        .map(|(key, s)| KeyRow {
+            key,
+            hits: s.hits,
+            misses: s.misses,
+            accuracy: s.accuracy(),
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&JsonExport {
+        sessions: results,
+        keys,
+    })
+    .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0), |(s, c), v| (s + v, c + 1));
+    match count {
+        0 => 0.0,
+        c => sum / c as f64,
+    }
+}
+
+// Totals, the best run of every song and difficulty, the trend day by day and the weakest keys.
+pub fn markdown_report(results: &[&SessionResult], range: &DateRange) -> String {
+    let mut md = String::from("# Typing report\n\n");
+
+    let minutes = results.iter().map(|r| r.seconds).sum::<f64>() / 60.0;
+    md.push_str(&format!(
+        "{}, {} sessions, {:.0} minutes typed.\n\n",
+        range.describe(),
+        results.len(),
+        minutes
+    ));
+
+    let best = results.iter().max_by(|a, b| a.wpm.total_cmp(&b.wpm));
+    if let Some(best) = best {
+        md.push_str(&format!(
+            "Average {:.1} WPM at {:.1}% accuracy, best {:.1} WPM on {} by {}.\n\n",
+            mean(results.iter().map(|r| r.wpm)),
+            mean(results.iter().map(|r| r.accuracy)),
+            best.wpm,
+            best.title,
+            best.artist
+        ));
+    }
+
+    let mut songs: BTreeMap<(String, String, Difficulty), Vec<&SessionResult>> = BTreeMap::new();
+    for r in results {
+        songs
+            .entry((r.title.to_owned(), r.artist.to_owned(), r.difficulty))
+            .or_default()
+            .push(r);
+    }
+
+    md.push_str("## Best per song\n\n");
+    md.push_str("| Song | Artist | Difficulty | Best WPM | Accuracy | Sessions |\n");
+    md.push_str("|---|---|---|---:|---:|---:|\n");
+    for ((title, artist, difficulty), runs) in songs.iter() {
+        let best = runs.iter().max_by(|a, b| a.wpm.total_cmp(&b.wpm));
+        if let Some(best) = best {
+            md.push_str(&format!(
+                "| {} | {} | {} | {:.1} | {:.1}% | {} |\n",
+                md_cell(title),
+                md_cell(artist),
+                difficulty.name(),
+                best.wpm,
+                best.accuracy,
+                runs.len()
+            ));
+        }
+    }
+
+    let mut days: BTreeMap<u64, Vec<&SessionResult>> = BTreeMap::new();
+    for r in results {
+        days.entry(r.finished_at / DAY).or_default().push(r);
+    }
+
+    md.push_str("\n## Trend\n\n");
+    md.push_str("| Day | Sessions | Average WPM | Best WPM | Accuracy |\n");
+    md.push_str("|---|---:|---:|---:|---:|\n");
+    for (day, runs) in days.iter() {
+        md.push_str(&format!(
+            "| {} | {} | {:.1} | {:.1} | {:.1}% |\n",
+            format_date(day * DAY),
+            runs.len(),
+            mean(runs.iter().map(|r| r.wpm)),
+            runs.iter().map(|r| r.wpm).fold(0.0, f64::max),
+            mean(runs.iter().map(|r| r.accuracy))
+        ));
+    }
+
+    let mut keys: Vec<(char, KeyStats)> = key_totals(results)
+        .into_iter()
+        .filter(|(_, s)| s.misses > 0)
+        .collect();
+    keys.sort_by(|a, b| b.1.misses.cmp(&a.1.misses).then(a.0.cmp(&b.0)));
+    keys.truncate(10);
+
+    if !keys.is_empty() {
+        md.push_str("\n## Most missed keys\n\n");
+        md.push_str("| Key | Misses | Accuracy |\n");
+        md.push_str("|---|---:|---:|\n");
+        for (key, stats) in keys {
+            md.push_str(&format!(
+                "| `{}` | {} | {:.1}% |\n",
+                key_name(key),
+                stats.misses,
+                stats.accuracy()
+            ));
+        }
+    }
+
+    md
+}
+
+// Writes the sessions in the range to `path`, csv exports put the per key stats next to it in
+// `<name>-keys.csv`. Returns every file written.
+pub fn export(
+    path: &Path,
+    format: ExportFormat,
+    results: &[SessionResult],
+    range: &DateRange,
+) -> Result<Vec<PathBuf>> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let results = in_range(results, range);
+    match format {
+        ExportFormat::Csv => {
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let keys_path = path.with_file_name(format!("{}-keys.csv", stem));
+
+            std::fs::write(path, sessions_csv(&results))?;
+            std::fs::write(&keys_path, keys_csv(&results))?;
+            Ok(vec![path.to_path_buf(), keys_path])
+        }
+        ExportFormat::Json => {
+            std::fs::write(path, to_json(&results)?)?;
+            Ok(vec![path.to_path_buf()])
+        }
+        ExportFormat::Markdown => {
+            std::fs::write(path, markdown_report(&results, range))?;
+            Ok(vec![path.to_path_buf()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, wpm: f64, finished_at: u64) -> SessionResult {
+        SessionResult {
+            song_id: None,
+            title: title.to_owned(),
+            artist: String::from("Black Sabbath"),
+            difficulty: Difficulty::Standard,
+            normalization: String::new(),
+            wpm,
+            accuracy: 90.0,
+            correct_hits: 9,
+            total_hits: 10,
+            seconds: 60.0,
+            finished_at,
+            recording: None,
+            provider: None,
+            keys: BTreeMap::from([(' ', KeyStats { hits: 3, misses: 1 })]),
+            excerpt: None,
+        }
+    }
+
+    #[test]
+    fn dates_round_trip() {
+        let day = parse_date("2026-10-19").unwrap();
+        assert_eq!(day, 1_792_368_000);
+        assert_eq!(format_date(day + DAY - 1), "2026-10-19");
+        assert_eq!(format_date(parse_date("2000-02-29").unwrap()), "2000-02-29");
+        assert_eq!(parse_date("2026-13-01"), None);
+    }
+
+    #[test]
+    fn ranges_include_the_last_day() {
+        let range = DateRange::parse(Some("2026-10-12"), Some("2026-10-18")).unwrap();
+        assert!(range.contains(parse_date("2026-10-18").unwrap() + DAY - 1));
+        assert!(!range.contains(parse_date("2026-10-19").unwrap()));
+        assert_eq!(range.describe(), "2026-10-12 to 2026-10-18");
+        assert!(DateRange::parse(Some("last week"), None).is_err());
+    }
+
+    #[test]
+    fn reports_show_bests_and_trends() {
+        let day = parse_date("2026-10-19").unwrap();
+        let results = [
+            result("Paranoid", 60.0, day),
+            result("Paranoid", 70.0, day + DAY),
+            result("War Pigs, live", 50.0, day + DAY),
+        ];
+        let results: Vec<&SessionResult> = results.iter().collect();
+
+        let md = markdown_report(&results, &DateRange::default());
+        assert!(md.contains("| Paranoid | Black Sabbath | standard | 70.0 | 90.0% | 2 |"));
+        assert!(md.contains("| 2026-10-20 | 2 | 60.0 | 70.0 | 90.0% |"));
+        assert!(md.contains("| `space` | 3 | 75.0% |"));
+
+        let csv = sessions_csv(&results);
+        assert!(csv.lines().nth(3).unwrap().contains(",\"War Pigs, live\","));
+        assert_eq!(
+            keys_csv(&results),
+            "key,hits,misses,accuracy\nspace,9,3,75.0\n"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::difficulty::Difficulty;
+use crate::library::data_dir;
+use crate::provider::ProviderName;
+use crate::stats::{KeyStats, SessionStats};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionResult {
+    pub song_id: Option<String>,
+    pub title: String,
+    pub artist: String,
+    pub difficulty: Difficulty,
+    pub normalization: String,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub correct_hits: u32,
+    pub total_hits: u32,
+    pub seconds: f64,
+    pub finished_at: u64,
+    #[serde(default)]
+    pub recording: Option<PathBuf>,
+    // Where the lyrics came from.
+    #[serde(default)]
+    pub provider: Option<ProviderName>,
+    // Hits and misses by the character which should have been typed.
+    #[serde(default)]
+    pub keys: BTreeMap<char, KeyStats>,
+    // The part of the song typed when it was not all of it, see `Excerpt::describe`.
+    #[serde(default)]
+    pub excerpt: Option<String>,
+}
+
+pub fn history_path() -> PathBuf {
+    data_dir().join("history.jsonl")
+}
+
+// One json result per line so a crash never loses more than the session being written.
+pub fn append_result(path: &Path, result: &SessionResult) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let line = serde_json::to_string(result).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)
+}
+
+pub fn load_history(path: &Path) -> Result<Vec<SessionResult>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+        .collect()
+}
+
+// Scores are only comparable within the same difficulty, and over the whole song.
+pub fn personal_best<'a>(
+    results: &'a [SessionResult],
+    song_id: &str,
+    difficulty: Difficulty,
+) -> Option<&'a SessionResult> {
+    results
+        .iter()
+        .filter(|r| r.song_id.as_deref() == Some(song_id) && r.difficulty == difficulty)
+        .filter(|r| r.excerpt.is_none())
+        .max_by(|a, b| a.wpm.total_cmp(&b.wpm))
+}
+
+// Average speed of the last `count` sessions at the difficulty.
+pub fn recent_wpm(results: &[SessionResult], difficulty: Difficulty, count: usize) -> Option<f64> {
+    let recent: Vec<f64> = results
+        .iter()
+        .rev()
+        .filter(|r| r.difficulty == difficulty)
+        .take(count)
+        .map(|r| r.wpm)
+        .collect();
+
+    match recent.len() {
+        0 => None,
+        n => Some(recent.iter().sum::<f64>() / n as f64),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionReport {
+    pub result: SessionResult,
+    pub stats: SessionStats,
+}
+
+pub fn reports_dir() -> PathBuf {
+    data_dir().join("results")
+}
+
+pub fn save_report(dir: &Path, report: &SessionReport) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+
+    let name = match report.result.song_id.as_ref() {
+        Some(id) => format!("{}-{}.json", report.result.finished_at, id),
+        None => format!("{}.json", report.result.finished_at),
+    };
+    let path = dir.join(name);
+
+    let s =
+        serde_json::to_string_pretty(report).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    std::fs::write(&path, s)?;
+    Ok(path)
+}
@@ -1,7 +1,33 @@
+pub mod config;
+pub mod credentials;
+pub mod daily;
+pub mod difficulty;
+pub mod excerpt;
+pub mod export;
+pub mod history;
+pub mod library;
+pub mod lrclib;
+pub mod normalize;
+pub mod playlist;
+pub mod provider;
+pub mod race;
+pub mod recording;
 pub mod response;
+pub mod retry;
+pub mod search;
+pub mod session;
+pub mod stats;
+pub mod theme;
+pub mod timed;
 
 use core::future::Future;
-use reqwest::{header::HeaderMap, Client, Error, Response};
+use credentials::Credentials;
+use reqwest::{header::HeaderMap, Client, Error, Response, StatusCode};
+use response::Root;
+use retry::{retry_after, Quota, RequestBudget, RequestPolicy};
+use search::{parse_query, query_title_artist};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 const URL: &str = "https://musixmatch-lyrics-songs.p.rapidapi.com/songs/lyrics";
 
@@ -10,49 +36,194 @@ pub trait Lyrics {
     fn get_lyrics(&self, query: String) -> impl Future<Output = Self::Out>;
 }
 
-impl Lyrics for Client {
-    type Out = Result<Response, Error>;
+// The provider endpoint and the client sending to it, the url can point at a mirror or a mock.
+#[derive(Debug, Clone)]
+pub struct Musixmatch {
+    pub client: Client,
+    pub base_url: String,
+    pub policy: RequestPolicy,
+    // Only kept to take the key out of the messages of failed lookups.
+    pub credentials: Option<Credentials>,
 
-    async fn get_lyrics(&self, query: String) -> Result<Response, Error> {
-        let v: Vec<&str> = query.split(',').flat_map(|s| s.trim().split(':')).collect();
+    budget: Arc<Mutex<RequestBudget>>,
+    quota: Arc<Mutex<Option<Quota>>>,
+}
 
-        let mut q_vec: Vec<(&str, &str)> = Vec::new();
+enum Attempt {
+    Done(Result<Root, String>),
+    // Worth another try, with the wait the provider asked for if it did.
+    Retry(String, Option<Duration>),
+}
+
+impl Musixmatch {
+    pub fn new(client: Client, base_url: Option<String>) -> Self {
+        Musixmatch {
+            client,
+            base_url: base_url.unwrap_or_else(|| URL.to_owned()),
+            policy: RequestPolicy::default(),
+            credentials: None,
+            budget: Arc::default(),
+            quota: Arc::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+        self
+    }
 
-        for (index, val) in v.iter().enumerate() {
-            if val.eq_ignore_ascii_case("t") {
-                let vals = v.get(index + 1).expect("VALUE FAILED");
-                q_vec.push(("t", vals.trim()));
-            } else if val.eq_ignore_ascii_case("a") {
-                let vals = v.get(index + 1).expect("VALUE FAILED");
-                q_vec.push(("a", vals.trim()));
+    // Quota of the plan as of the last response which had it.
+    pub fn quota(&self) -> Option<Quota> {
+        self.quota.lock().ok().and_then(|q| *q)
+    }
+
+    async fn attempt(&self, query: &str) -> Attempt {
+        let resp = match self.get_lyrics(query.to_owned()).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let error = format!("Could not get the song you requested, please search a different song, probably some black sabbath: Error ->  {}", e);
+                return match e.is_timeout() || e.is_connect() {
+                    true => Attempt::Retry(error, None),
+                    false => Attempt::Done(Err(error)),
+                };
+            }
+        };
+
+        if let Some(quota) = Quota::from_headers(resp.headers()) {
+            if let Ok(mut q) = self.quota.lock() {
+                *q = Some(quota);
             }
         }
 
-        q_vec.push(("type", "json"));
+        match resp.status() {
+            StatusCode::NOT_FOUND => {
+                return Attempt::Done(Err(String::from(
+                    "The song was not found, please search a different song.",
+                )))
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Attempt::Retry(
+                    String::from(
+                        "Too many requests to the lyrics provider, try again in a moment.",
+                    ),
+                    retry_after(resp.headers()),
+                )
+            }
+            status if status.is_server_error() => {
+                return Attempt::Retry(
+                    format!("The lyrics provider answered {}", status),
+                    retry_after(resp.headers()),
+                )
+            }
+            status if !status.is_success() => {
+                return Attempt::Done(Err(format!("The lyrics provider answered {}", status)))
+            }
+            _ => (),
+        }
 
-        assert_eq!(q_vec.len(), 3);
-        self.get(URL).query(&q_vec).send().await
+        Attempt::Done(match resp.json::<Root>().await {
+            Ok(root) if root.is_empty() => Err(String::from(
+                "The song has no lyrics, search for another one.",
+            )),
+            Ok(root) => Ok(root),
+            Err(e) => Err(format!("Failed to deserialize {}", e)),
+        })
     }
-}
 
-pub fn generate_client() -> Result<Client, reqwest::Error> {
-    let v: Vec<_> = std::env::vars()
-        .filter(|k| k.0.contains("x_rapid_api"))
-        .collect();
-
-    let mut header = HeaderMap::new();
-
-    for (key, vals) in v.iter() {
-        if key.eq("x_rapid_api_key") {
-            assert!(header
-                .insert("x-rapidapi-key", vals.parse().unwrap())
-                .is_none());
-        } else {
-            assert!(header
-                .insert("x-rapidapi-host", vals.parse().unwrap())
-                .is_none());
+    // The lyrics of the song, failures come back as a message for the user. Timeouts, rate limits
+    // and server errors are retried with backoff since a lookup can be repeated safely.
+    pub async fn fetch_root(&self, query: &str) -> Result<Root, String> {
+        if query_title_artist(query).0.is_empty() {
+            return Err(format!(
+                "{} has no title, search with t: <title>, a: <artist>.",
+                query.trim()
+            ));
+        }
+
+        self.fetch_with_retries(query)
+            .await
+            .map_err(|e| match &self.credentials {
+                Some(c) => c.redact(&e),
+                None => e,
+            })
+    }
+
+    async fn fetch_with_retries(&self, query: &str) -> Result<Root, String> {
+        let mut retry = 0;
+        loop {
+            let budget = match self.budget.lock() {
+                Ok(mut b) => b.take(self.policy.requests_per_minute, Instant::now()),
+                Err(_) => Ok(()),
+            };
+            if let Err(wait) = budget {
+                return Err(format!(
+                    "Request budget of {} a minute used up, try again in {} seconds.",
+                    self.policy.requests_per_minute,
+                    wait.as_secs().max(1)
+                ));
+            }
+
+            let (error, wait) = match self.attempt(query).await {
+                Attempt::Done(result) => return result,
+                Attempt::Retry(error, wait) => (error, wait),
+            };
+
+            retry += 1;
+            if retry >= self.policy.attempts {
+                return Err(error);
+            }
+
+            let wait = wait.unwrap_or_else(|| self.policy.backoff(retry - 1));
+            if wait > self.policy.max_delay() {
+                return Err(format!("{} Retry in {} seconds.", error, wait.as_secs()));
+            }
+            tokio::time::sleep(wait).await;
         }
     }
+}
+
+impl Lyrics for Musixmatch {
+    type Out = Result<Response, Error>;
+
+    // Only the keys of the query which are there are sent, `fetch_root` asks for a title first.
+    async fn get_lyrics(&self, query: String) -> Result<Response, Error> {
+        let mut params: Vec<(&str, &str)> = parse_query(&query)
+            .into_iter()
+            .filter_map(|(k, v)| match k.to_ascii_lowercase().as_str() {
+                "t" => Some(("t", v)),
+                "a" => Some(("a", v)),
+                _ => None,
+            })
+            .collect();
+        params.push(("type", "json"));
 
-    Client::builder().default_headers(header).build()
+        self.client.get(&self.base_url).query(&params).send().await
+    }
+}
+
+// Without credentials the client still works against a mirror or mock which does not ask for them.
+pub fn generate_client(
+    credentials: Option<&Credentials>,
+    policy: &RequestPolicy,
+) -> Result<Client, String> {
+    let header = match credentials {
+        Some(c) => c.headers()?,
+        None => HeaderMap::new(),
+    };
+
+    let builder = Client::builder().default_headers(header);
+    match policy.timeout() {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    }
+    .build()
+    .map_err(|e| match credentials {
+        Some(c) => c.redact(&e.to_string()),
+        None => e.to_string(),
+    })
 }
@@ -0,0 +1,391 @@
+use crate::difficulty::Difficulty;
+use crate::lrclib::parse_synced;
+use crate::response::{root_from_text, Root};
+use crate::search::{filter_candidates, parse_query, Candidate, CandidateSource};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const INDEX_FILE: &str = "index.json";
+const LYRICS_DIR: &str = "lyrics";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntryOrigin {
+    Cache,
+    Imported(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    pub album: Option<String>,
+    pub duration: Option<f64>,
+    pub synced: bool,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub last_played: Option<u64>,
+    // Best wpm per difficulty, scores of different difficulties are not comparable.
+    pub best_wpm: BTreeMap<Difficulty, f64>,
+    pub origin: EntryOrigin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Artist,
+    Title,
+    LastPlayed,
+    BestWpm,
+}
+
+impl SortKey {
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Artist => SortKey::Title,
+            SortKey::Title => SortKey::LastPlayed,
+            SortKey::LastPlayed => SortKey::BestWpm,
+            SortKey::BestWpm => SortKey::Artist,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Artist => "artist",
+            SortKey::Title => "title",
+            SortKey::LastPlayed => "last played",
+            SortKey::BestWpm => "best wpm",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Library {
+    pub dir: PathBuf,
+    pub entries: Vec<LibraryEntry>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// LYRICIST_DATA_DIR wins, otherwise the platform data directory is used.
+pub fn data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("LYRICIST_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        return PathBuf::from(dir).join("lyricist");
+    }
+
+    if let Ok(home) = std::env::var("HOME") {
+        return PathBuf::from(home).join(".local/share/lyricist");
+    }
+
+    if let Ok(app_data) = std::env::var("APPDATA") {
+        return PathBuf::from(app_data).join("lyricist");
+    }
+
+    PathBuf::from(".lyricist")
+}
+
+fn make_id(title: &str, artist: &str) -> String {
+    format!("{}-{}", artist, title)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+fn invalid_data(e: serde_json::Error) -> Error {
+    Error::new(ErrorKind::InvalidData, e)
+}
+
+impl Library {
+    pub fn default_dir() -> PathBuf {
+        data_dir().join("library")
+    }
+
+    pub fn empty(dir: PathBuf) -> Self {
+        Library {
+            dir,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn load(dir: PathBuf) -> Result<Self> {
+        let index = dir.join(INDEX_FILE);
+        if !index.exists() {
+            return Ok(Library::empty(dir));
+        }
+
+        let entries =
+            serde_json::from_str(&std::fs::read_to_string(index)?).map_err(invalid_data)?;
+        Ok(Library { dir, entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let s = serde_json::to_string_pretty(&self.entries).map_err(invalid_data)?;
+        std::fs::write(self.dir.join(INDEX_FILE), s)
+    }
+
+    pub fn lyrics_path(&self, id: &str) -> PathBuf {
+        self.dir.join(LYRICS_DIR).join(format!("{}.json", id))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LibraryEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    pub fn find(&self, title: &str, artist: &str) -> Option<&LibraryEntry> {
+        self.get(&make_id(title, artist))
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut LibraryEntry> {
+        self.entries.iter_mut().find(|e| e.id == id)
+    }
+
+    // Adding a song which already exists refreshes its lyrics but keeps the stats and tags.
+    pub fn add(
+        &mut self,
+        title: &str,
+        artist: &str,
+        album: Option<String>,
+        root: &Root,
+        origin: EntryOrigin,
+    ) -> Result<String> {
+        let id = make_id(title, artist);
+        let path = self.lyrics_path(&id);
+
+        std::fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        std::fs::write(&path, serde_json::to_string(root).map_err(invalid_data)?)?;
+
+        let duration = root.last().map(|r| r.time.total).filter(|d| *d > 0.0);
+        let synced = root.iter().any(|r| r.time.total > 0.0);
+
+        match self.get_mut(&id) {
+            Some(entry) => {
+                entry.album = album.or(entry.album.take());
+                entry.duration = duration;
+                entry.synced = synced;
+            }
+            None => self.entries.push(LibraryEntry {
+                id: id.to_owned(),
+                title: title.to_owned(),
+                artist: artist.to_owned(),
+                album,
+                duration,
+                synced,
+                tags: Vec::new(),
+                favorite: false,
+                last_played: None,
+                best_wpm: BTreeMap::new(),
+                origin,
+            }),
+        }
+
+        self.save()?;
+        Ok(id)
+    }
+
+    // Imports a json, LRC or plain text lyrics file, the file name is used as `<artist> - <title>`.
+    pub fn import_file(&mut self, path: &Path) -> Result<String> {
+        let text = std::fs::read_to_string(path)?;
+        let root: Root = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(invalid_data)?,
+            Some("lrc") => parse_synced(&text),
+            _ => root_from_text(&text),
+        };
+        if root.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "the file has no lines to type",
+            ));
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let (artist, title) = match stem.split_once(" - ") {
+            Some((a, t)) => (a.trim().to_owned(), t.trim().to_owned()),
+            None => (String::from("Unknown"), stem.trim().to_owned()),
+        };
+
+        self.add(
+            &title,
+            &artist,
+            None,
+            &root,
+            EntryOrigin::Imported(path.to_path_buf()),
+        )
+    }
+
+    pub fn load_root(&self, id: &str) -> Result<Root> {
+        serde_json::from_str(&std::fs::read_to_string(self.lyrics_path(id))?).map_err(invalid_data)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Result<()> {
+        let path = self.lyrics_path(id);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        self.entries.retain(|e| e.id != id);
+        self.save()
+    }
+
+    pub fn toggle_favorite(&mut self, id: &str) -> Result<()> {
+        if let Some(entry) = self.get_mut(id) {
+            entry.favorite = !entry.favorite;
+        }
+
+        self.save()
+    }
+
+    pub fn toggle_tag(&mut self, id: &str, tag: &str) -> Result<()> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(entry) = self.get_mut(id) {
+            if entry.tags.iter().any(|t| t == tag) {
+                entry.tags.retain(|t| t != tag);
+            } else {
+                entry.tags.push(tag.to_owned());
+            }
+        }
+
+        self.save()
+    }
+
+    pub fn record_play(&mut self, id: &str, wpm: f64, difficulty: Difficulty) -> Result<()> {
+        if let Some(entry) = self.get_mut(id) {
+            entry.last_played = Some(unix_now());
+            if entry
+                .best_wpm
+                .get(&difficulty)
+                .is_none_or(|best| wpm > *best)
+            {
+                entry.best_wpm.insert(difficulty, wpm);
+            }
+        }
+
+        self.save()
+    }
+
+    // Favorites always come first, the sort key decides the rest.
+    pub fn sorted(&self, key: SortKey, difficulty: Difficulty) -> Vec<&LibraryEntry> {
+        let mut entries: Vec<&LibraryEntry> = self.entries.iter().collect();
+
+        entries.sort_by(|a, b| {
+            b.favorite.cmp(&a.favorite).then_with(|| match key {
+                SortKey::Artist => a
+                    .artist
+                    .to_lowercase()
+                    .cmp(&b.artist.to_lowercase())
+                    .then_with(|| a.title.to_lowercase().cmp(&b.title.to_lowercase())),
+                SortKey::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+                SortKey::LastPlayed => b.last_played.cmp(&a.last_played),
+                SortKey::BestWpm => {
+                    let best = |e: &LibraryEntry| e.best_wpm.get(&difficulty).copied();
+                    best(b).unwrap_or(0.0).total_cmp(&best(a).unwrap_or(0.0))
+                }
+            })
+        });
+
+        entries
+    }
+
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.entries
+            .iter()
+            .map(|e| Candidate {
+                title: e.title.to_owned(),
+                artist: e.artist.to_owned(),
+                album: e.album.to_owned(),
+                duration: e.duration,
+                synced: e.synced,
+                source: CandidateSource::Local {
+                    id: e.id.to_owned(),
+                    path: self.lyrics_path(&e.id),
+                },
+            })
+            .collect()
+    }
+
+    // Matches the library against a `t: <title>, a: <artist>` search request.
+    pub fn search(&self, query: &str) -> Vec<Candidate> {
+        let fields = parse_query(query);
+        // A query without `t:` or `a:` is matched as it is typed, an empty pattern matches everything.
+        let pattern = match fields.is_empty() {
+            true => query.trim().to_owned(),
+            false => fields
+                .iter()
+                .map(|(_, v)| *v)
+                .collect::<Vec<&str>>()
+                .join(" "),
+        };
+
+        let candidates = self.candidates();
+        filter_candidates(&candidates, &pattern)
+            .into_iter()
+            .map(|i| candidates[i].to_owned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Root2;
+
+    #[test]
+    fn plain_queries_are_matched_as_typed() {
+        let dir = std::env::temp_dir().join(format!("lyricist-search-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut library = Library::empty(dir.to_owned());
+        let lyrics = vec![Root2::line("la")];
+        library
+            .add(
+                "Paranoid",
+                "Black Sabbath",
+                None,
+                &lyrics,
+                EntryOrigin::Cache,
+            )
+            .unwrap();
+        library
+            .add(
+                "Iron Man",
+                "Black Sabbath",
+                None,
+                &lyrics,
+                EntryOrigin::Cache,
+            )
+            .unwrap();
+
+        let titles = |query: &str| -> Vec<String> {
+            library.search(query).into_iter().map(|c| c.title).collect()
+        };
+        assert_eq!(titles("paranoid"), vec!["Paranoid"]);
+        assert_eq!(titles("t: iron"), vec!["Iron Man"]);
+        assert_eq!(titles("").len(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
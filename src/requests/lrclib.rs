@@ -0,0 +1,240 @@
+use crate::response::{Root, Root2, Time};
+use crate::search::parse_query;
+use crate::Lyrics;
+use reqwest::{Client, Error, Response, StatusCode};
+use serde_derive::Deserialize;
+
+const URL: &str = "https://lrclib.net";
+
+// A server speaking the LRCLIB api, the public one or a self hosted mirror.
+#[derive(Debug, Clone)]
+pub struct Lrclib {
+    pub client: Client,
+    pub base_url: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LrclibTrack {
+    pub id: u64,
+    pub track_name: String,
+    pub artist_name: String,
+    pub album_name: Option<String>,
+    pub duration: Option<f64>,
+    pub instrumental: bool,
+    pub plain_lyrics: Option<String>,
+    pub synced_lyrics: Option<String>,
+}
+
+impl LrclibTrack {
+    // Synced lyrics when the track has them, the plain ones otherwise.
+    pub fn to_root(&self) -> Root {
+        match (&self.synced_lyrics, &self.plain_lyrics) {
+            (Some(synced), _) if !synced.trim().is_empty() => parse_synced(synced),
+            (_, Some(plain)) => parse_plain(plain),
+            _ => Root::new(),
+        }
+    }
+}
+
+fn time_at(total: f64) -> Time {
+    let hundredths = (total * 100.0).round() as i64;
+    Time {
+        total,
+        minutes: hundredths / 6000,
+        seconds: hundredths / 100 % 60,
+        hundredths: hundredths % 100,
+    }
+}
+
+// `[mm:ss.xx]` or `[mm:ss.xxx]`, tags like `[ar: Black Sabbath]` are not times.
+fn parse_timestamp(tag: &str) -> Option<f64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds: f64 = seconds.trim().parse().ok()?;
+
+    Some(minutes as f64 * 60.0 + seconds)
+}
+
+// LRC lines, a line with several timestamps is sung once at each of them.
+pub fn parse_synced(lrc: &str) -> Root {
+    let mut root = Root::new();
+
+    for line in lrc.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+
+        while let Some(tag) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            match parse_timestamp(tag.0) {
+                Some(total) => times.push(total),
+                None => break,
+            }
+            rest = tag.1;
+        }
+
+        root.extend(times.into_iter().map(|total| Root2 {
+            text: rest.trim().to_owned(),
+            time: time_at(total),
+        }));
+    }
+
+    root.sort_by(|a, b| a.time.total.total_cmp(&b.time.total));
+    root
+}
+
+pub fn parse_plain(text: &str) -> Root {
+    text.lines().map(|l| Root2::line(l.trim())).collect()
+}
+
+// The LRCLIB parameters of a `t: <title>, a: <artist>, al: <album>, d: <seconds>` query.
+fn track_params(query: &str) -> Vec<(&'static str, String)> {
+    parse_query(query)
+        .into_iter()
+        .filter_map(|(k, v)| match k.to_ascii_lowercase().as_str() {
+            "t" => Some(("track_name", v.to_owned())),
+            "a" => Some(("artist_name", v.to_owned())),
+            "al" => Some(("album_name", v.to_owned())),
+            "d" => Some(("duration", v.to_owned())),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Lrclib {
+    pub fn new(client: Client, base_url: Option<String>) -> Self {
+        Lrclib {
+            client,
+            base_url: base_url
+                .unwrap_or_else(|| URL.to_owned())
+                .trim_end_matches('/')
+                .to_owned(),
+        }
+    }
+
+    // None when LRCLIB does not have it, every other failure is an error.
+    async fn send(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> Result<Option<Response>, String> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(params)
+            .send()
+            .await
+            .map_err(|e| format!("Could not reach LRCLIB: Error -> {}", e))?;
+
+        match resp.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(format!("LRCLIB answered {}", status)),
+            _ => Ok(Some(resp)),
+        }
+    }
+
+    // The track matching the title and artist, and the album and duration when the query has them,
+    // None when there is no such track.
+    pub async fn get(&self, query: &str) -> Result<Option<LrclibTrack>, String> {
+        match self.send("/api/get", &track_params(query)).await? {
+            Some(resp) => resp
+                .json()
+                .await
+                .map(Some)
+                .map_err(|e| format!("Failed to deserialize {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    // Every track close to the query, the best matches first.
+    pub async fn search(&self, query: &str) -> Result<Vec<LrclibTrack>, String> {
+        let params: Vec<_> = track_params(query)
+            .into_iter()
+            .filter(|(k, _)| matches!(*k, "track_name" | "artist_name" | "album_name"))
+            .collect();
+
+        match self.send("/api/search", &params).await? {
+            Some(resp) => resp
+                .json()
+                .await
+                .map_err(|e| format!("Failed to deserialize {}", e)),
+            None => Ok(vec![]),
+        }
+    }
+
+    // An exact match with lyrics first, the best search result with lyrics when there is none.
+    // Other failures of the exact match, an outage or the rate limit, are not worth a search.
+    pub async fn fetch_root(&self, query: &str) -> Result<Root, String> {
+        let has_lyrics = |t: &LrclibTrack| !t.instrumental && !t.to_root().is_empty();
+        let track = match self.get(query).await?.filter(has_lyrics) {
+            Some(track) => track,
+            None => self
+                .search(query)
+                .await?
+                .into_iter()
+                .find(has_lyrics)
+                .ok_or_else(|| {
+                    String::from("The song was not found, please search a different song.")
+                })?,
+        };
+
+        Ok(track.to_root())
+    }
+}
+
+impl Lyrics for Lrclib {
+    type Out = Result<Response, Error>;
+
+    async fn get_lyrics(&self, query: String) -> Result<Response, Error> {
+        self.client
+            .get(format!("{}/api/get", self.base_url))
+            .query(&track_params(&query))
+            .send()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synced_lyrics_become_timed_lines() {
+        let root = parse_synced(
+            "[ar: Black Sabbath]\n[00:12.50] Finished with my woman\n[00:15.10]\n[01:02.34][00:20.00] Can you help me",
+        );
+
+        let lines: Vec<(&str, f64)> = root
+            .iter()
+            .map(|r| (r.text.as_str(), r.time.total))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("Finished with my woman", 12.5),
+                ("", 15.1),
+                ("Can you help me", 20.0),
+                ("Can you help me", 62.34),
+            ]
+        );
+        assert_eq!(
+            root[3].time,
+            Time {
+                total: 62.34,
+                minutes: 1,
+                seconds: 2,
+                hundredths: 34,
+            }
+        );
+    }
+
+    #[test]
+    fn plain_lyrics_are_used_without_synced_ones() {
+        let track = LrclibTrack {
+            plain_lyrics: Some(String::from("One\nTwo")),
+            synced_lyrics: Some(String::from(" ")),
+            ..Default::default()
+        };
+        assert_eq!(track.to_root(), parse_plain("One\nTwo"));
+        assert_eq!(track.to_root()[1].time.total, 0.0);
+    }
+}
@@ -1,30 +1,154 @@
-use ratatui::{
-    crossterm::terminal::enable_raw_mode, layout::Rect, prelude::CrosstermBackend, style::Stylize,
-    text::Line, widgets::Paragraph, Terminal,
+use libreq::{
+    config::{config_path, Config},
+    credentials::{resolve, save_secrets, secrets_path, Credentials},
+    export::{export, DateRange, ExportFormat},
+    generate_client,
+    history::{history_path, load_history},
+    Lyrics, Musixmatch,
 };
-use std::io::stdout;
+use reqwest::StatusCode;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::process::exit;
 
-mod response;
+const USAGE: &str = "usage: req auth set [--key <key>] [--host <host>]
+       req auth check
+       req export <file.csv|file.json|file.md> [--format csv|json|md] [--from YYYY-MM-DD] [--to YYYY-MM-DD]";
+
+// A well known song, any answer but a refusal means the key works.
+const CHECK_QUERY: &str = "t: Paranoid, a: Black Sabbath";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|v| v.to_owned())
+}
+
+// Asked for on stdin when not given as a flag, so the key stays out of the shell history.
+fn prompt(label: &str) -> String {
+    print!("{}: ", label);
+    let _ = stdout().flush();
+
+    let mut line = String::new();
+    if let Err(e) = stdin().lock().read_line(&mut line) {
+        fail(&format!("Failed to read the {}, Error {}", label, e));
+    }
+    line.trim().to_owned()
+}
+
+fn set(args: &[String]) {
+    let key = flag(args, "--key").unwrap_or_else(|| prompt("RapidAPI key"));
+    let host = flag(args, "--host").unwrap_or_else(|| prompt("RapidAPI host"));
+
+    let credentials = Credentials::new(&key, &host);
+    if credentials.key.is_empty() || credentials.host.is_empty() {
+        fail("Both the key and the host are needed.");
+    }
+    if let Err(e) = credentials.headers() {
+        fail(&e);
+    }
+
+    let path = secrets_path();
+    match save_secrets(&path, &credentials) {
+        Ok(()) => println!(
+            "Saved the key {} to {}, only you can read it.",
+            credentials.masked_key(),
+            path.display()
+        ),
+        Err(e) => fail(&format!("Failed to save {}, Error {}", path.display(), e)),
+    }
+}
+
+async fn check() {
+    let config = match Config::load(&config_path()) {
+        Ok(c) => c,
+        Err(e) => fail(&format!(
+            "Failed to read the config {}, Error {}",
+            config_path().display(),
+            e
+        )),
+    };
+
+    let (credentials, source) = match resolve(&config) {
+        Ok(Some(found)) => found,
+        Ok(None) => fail("No provider credentials, run req auth set or set x_rapid_api_key and x_rapid_api_host."),
+        Err(e) => fail(&format!("Failed to read the provider credentials, Error {}", e)),
+    };
+    println!(
+        "Using the key {} for {} from {}.",
+        credentials.masked_key(),
+        credentials.host,
+        source
+    );
+
+    let client = match generate_client(Some(&credentials), &config.requests) {
+        Ok(c) => c,
+        Err(e) => fail(&format!("Failed to create the client, Error {}", e)),
+    };
+    let provider = Musixmatch::new(client, config.provider_url.to_owned());
+
+    match provider.get_lyrics(CHECK_QUERY.to_owned()).await {
+        Ok(resp) => match resp.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                fail("The provider refused the key, check it on RapidAPI.")
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                println!("The key works but the plan is out of requests for now.")
+            }
+            status if status.is_success() || status == StatusCode::NOT_FOUND => {
+                println!("The key works.")
+            }
+            status => fail(&format!("The provider answered {}", status)),
+        },
+        Err(e) => fail(&credentials.redact(&format!("Could not reach the provider, Error {}", e))),
+    }
+}
+
+// The history in the range, in the format of the extension unless --format says otherwise.
+fn export_history(args: &[String]) {
+    let path = match args.first().filter(|a| !a.starts_with("--")) {
+        Some(p) => std::path::PathBuf::from(p),
+        None => fail(USAGE),
+    };
+    let format = match flag(args, "--format") {
+        Some(name) => ExportFormat::from_name(&name),
+        None => ExportFormat::from_path(&path),
+    }
+    .unwrap_or_else(|| fail("The format is one of csv, json or md."));
+    let range = DateRange::parse(
+        flag(args, "--from").as_deref(),
+        flag(args, "--to").as_deref(),
+    )
+    .unwrap_or_else(|e| fail(&e));
+
+    let results = match load_history(&history_path()) {
+        Ok(r) => r,
+        Err(e) => fail(&format!("Failed to read the history, Error {}", e)),
+    };
+
+    match export(&path, format, &results, &range) {
+        Ok(paths) => {
+            for path in paths {
+                println!("Wrote {}", path.display());
+            }
+        }
+        Err(e) => fail(&format!("Failed to write {}, Error {}", path.display(), e)),
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    if let Err(e) = enable_raw_mode() {
-        panic!("Failed to enable raw mode Error: {}", e);
-    };
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    let mut terminal: Terminal<CrosstermBackend<_>> =
-        match Terminal::new(CrosstermBackend::new(stdout())) {
-            Ok(t) => t,
-            Err(e) => panic!("Failed to make a new terminal, Error {}", e),
-        };
-
-    let _ = terminal.clear();
-
-    let _ = terminal.draw(|f| {
-        let r: Rect = Rect::new(10, 10, 100, 10);
-        f.render_widget(
-            Paragraph::new(Line::from(vec!["Hello. ".red(), "world".green()])),
-            r,
-        );
-    });
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["auth", "set", ..] => set(&args[2..]),
+        ["auth", "check"] => check().await,
+        ["export", ..] => export_history(&args[1..]),
+        _ => fail(USAGE),
+    }
 }
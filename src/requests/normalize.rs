@@ -0,0 +1,254 @@
+use crate::response::Root;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizeOptions {
+    // Accented letters become their plain ascii letter, scripts without one are kept as they are.
+    pub ascii_fold: bool,
+    // Curly quotes, long dashes and the ellipsis character become their keyboard versions.
+    pub smart_punctuation: bool,
+    pub strip_punctuation: bool,
+    pub lowercase: bool,
+    // Removes `(Chorus)`, `[Verse 1]`, `(x2)` and the like, sung lines in brackets stay.
+    pub strip_cues: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            ascii_fold: true,
+            smart_punctuation: true,
+            strip_punctuation: false,
+            lowercase: false,
+            strip_cues: true,
+        }
+    }
+}
+
+impl NormalizeOptions {
+    pub fn describe(&self) -> String {
+        let enabled: Vec<&str> = [
+            (self.ascii_fold, "ascii"),
+            (self.smart_punctuation, "plain punctuation"),
+            (self.strip_punctuation, "no punctuation"),
+            (self.lowercase, "lowercase"),
+            (self.strip_cues, "no cues"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect();
+
+        if enabled.is_empty() {
+            String::from("raw")
+        } else {
+            enabled.join(", ")
+        }
+    }
+}
+
+fn replace_smart_punctuation(c: char) -> Option<&'static str> {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' | '`' | '\u{00B4}' => {
+            Some("'")
+        }
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' | '\u{00AB}'
+        | '\u{00BB}' => Some("\""),
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
+        | '\u{2212}' => Some("-"),
+        '\u{2026}' => Some("..."),
+        '\u{00A0}' | '\u{2009}' | '\u{200A}' | '\u{202F}' => Some(" "),
+        _ => None,
+    }
+}
+
+fn fold_to_ascii(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'ç' | 'ć' | 'č' | 'ĉ' => "c",
+        'Ç' | 'Ć' | 'Č' | 'Ĉ' => "C",
+        'ď' | 'đ' => "d",
+        'Ď' | 'Đ' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ğ' | 'ĝ' => "g",
+        'Ğ' | 'Ĝ' => "G",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ī' | 'Į' | 'İ' => "I",
+        'ł' | 'ľ' | 'ĺ' => "l",
+        'Ł' | 'Ľ' | 'Ĺ' => "L",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ő' => "O",
+        'ŕ' | 'ř' => "r",
+        'Ŕ' | 'Ř' => "R",
+        'ś' | 'š' | 'ş' => "s",
+        'Ś' | 'Š' | 'Ş' => "S",
+        'ť' | 'ţ' => "t",
+        'Ť' | 'Ţ' => "T",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ū' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        _ => return None,
+    };
+
+    Some(folded)
+}
+
+const CUE_WORDS: [&str; 12] = [
+    "chorus",
+    "pre-chorus",
+    "post-chorus",
+    "verse",
+    "bridge",
+    "intro",
+    "outro",
+    "hook",
+    "refrain",
+    "interlude",
+    "instrumental",
+    "repeat",
+];
+
+// `Chorus`, `Verse 2: Ozzy`, `x2` or `2x`, the insides of a bracket which are not sung.
+fn is_cue(inside: &str) -> bool {
+    let inside = inside.trim().to_lowercase();
+    let first = inside
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .next()
+        .unwrap_or_default();
+    let times =
+        |n: Option<&str>| n.is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+
+    CUE_WORDS.contains(&first) || times(inside.strip_prefix('x')) || times(inside.strip_suffix('x'))
+}
+
+// Only brackets holding a cue go, an unclosed bracket is left alone.
+fn strip_cues(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(open) = rest.find(['(', '[']) {
+        let close = match rest.as_bytes()[open] {
+            b'(' => ')',
+            _ => ']',
+        };
+        match rest[open + 1..].find(close) {
+            Some(len) if is_cue(&rest[open + 1..open + 1 + len]) => {
+                out.push_str(&rest[..open]);
+                rest = &rest[open + len + 2..];
+            }
+            _ => {
+                out.push_str(&rest[..open + 1]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+pub fn normalize_line(line: &str, options: &NormalizeOptions) -> String {
+    let mut line = if options.strip_cues {
+        strip_cues(line)
+    } else {
+        line.to_owned()
+    };
+
+    if options.smart_punctuation {
+        line = line
+            .chars()
+            .map(|c| match replace_smart_punctuation(c) {
+                Some(r) => r.to_owned(),
+                None => c.to_string(),
+            })
+            .collect();
+    }
+
+    if options.ascii_fold {
+        line = line
+            .chars()
+            .map(|c| match fold_to_ascii(c) {
+                Some(folded) => folded.to_owned(),
+                None => c.to_string(),
+            })
+            .collect();
+    }
+
+    if options.strip_punctuation {
+        line.retain(|c| !c.is_ascii_punctuation());
+    }
+
+    if options.lowercase {
+        line = line.to_lowercase();
+    }
+
+    line.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+pub fn normalize_root(root: &Root, options: &NormalizeOptions) -> Root {
+    root.iter()
+        .map(|r| {
+            let mut r = r.to_owned();
+            r.text = normalize_line(&r.text, options);
+            r
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_without_an_ascii_version_are_kept() {
+        let options = NormalizeOptions::default();
+        assert_eq!(
+            normalize_line("Café «déjà» vu…", &options),
+            "Cafe \"deja\" vu..."
+        );
+        assert_eq!(normalize_line("夜に駆ける", &options), "夜に駆ける");
+        assert_eq!(
+            normalize_line("Кино — Группа крови", &options),
+            "Кино - Группа крови"
+        );
+    }
+
+    #[test]
+    fn only_cues_are_stripped() {
+        let options = NormalizeOptions::default();
+        assert_eq!(normalize_line("[Chorus] Oh yeah (x2)", &options), "Oh yeah");
+        assert_eq!(normalize_line("(Verse 2: Ozzy) Hey", &options), "Hey");
+        assert_eq!(
+            normalize_line("I can't get no (I can't get no)", &options),
+            "I can't get no (I can't get no)"
+        );
+        assert_eq!(normalize_line("Open (bracket", &options), "Open (bracket");
+    }
+
+    #[test]
+    fn options_stack() {
+        let options = NormalizeOptions {
+            strip_punctuation: true,
+            lowercase: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize_line("  Don't   STOP! ", &options), "dont stop");
+        assert_eq!(
+            normalize_line("[Intro] Ça va?", &NormalizeOptions::default()),
+            "Ca va?"
+        );
+    }
+}
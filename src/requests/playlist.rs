@@ -0,0 +1,258 @@
+use std::io::Result;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaylistItem {
+    // A search request in the `t: <title>, a: <artist>` format.
+    Search(String),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatMode {
+    Off,
+    One,
+    All,
+}
+
+impl RepeatMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "off" => Some(RepeatMode::Off),
+            "one" => Some(RepeatMode::One),
+            "all" => Some(RepeatMode::All),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunStats {
+    pub songs_completed: u32,
+    pub correct_hits: u32,
+    pub total_hits: u32,
+    pub seconds: f64,
+}
+
+impl RunStats {
+    pub fn add_song(&mut self, correct_hits: u32, total_hits: u32, seconds: f64) {
+        self.songs_completed += 1;
+        self.correct_hits += correct_hits;
+        self.total_hits += total_hits;
+        self.seconds += seconds;
+    }
+
+    pub fn wpm(&self) -> f64 {
+        if self.seconds <= 0.0 {
+            return 0.0;
+        }
+
+        (self.correct_hits as f64 / 5.0) / (self.seconds / 60.0)
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.total_hits == 0 {
+            return 0.0;
+        }
+
+        self.correct_hits as f64 / self.total_hits as f64 * 100.0
+    }
+}
+
+// One entry per line, either a search request or the path of a lyrics json file. Empty lines and
+// lines starting with `#` are skipped.
+pub fn parse_playlist(content: &str, base_dir: &Path) -> Vec<PlaylistItem> {
+    content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let lower = l.to_lowercase();
+            if lower.starts_with("t:") || lower.starts_with("a:") {
+                PlaylistItem::Search(l.to_owned())
+            } else {
+                let path = PathBuf::from(l);
+                if path.is_relative() {
+                    PlaylistItem::File(base_dir.join(path))
+                } else {
+                    PlaylistItem::File(path)
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn load_playlist(path: &Path) -> Result<Vec<PlaylistItem>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_playlist(
+        &content,
+        path.parent().unwrap_or_else(|| Path::new(".")),
+    ))
+}
+
+// xorshift, good enough to shuffle a playlist without pulling in a rng crate.
+fn next_random(seed: &mut u64) -> u64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    *seed
+}
+
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut seed = seed.max(1);
+    for i in (1..items.len()).rev() {
+        let j = (next_random(&mut seed) % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Queue {
+    pub items: Vec<PlaylistItem>,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+    pub stats: RunStats,
+
+    order: Vec<usize>,
+    position: Option<usize>,
+    // Songs in a row which could not be loaded.
+    failed: usize,
+}
+
+impl Queue {
+    pub fn new(items: Vec<PlaylistItem>, shuffle: bool, repeat: RepeatMode) -> Self {
+        let mut queue = Queue {
+            order: (0..items.len()).collect(),
+            items,
+            shuffle,
+            repeat,
+            stats: RunStats::default(),
+            position: None,
+            failed: 0,
+        };
+
+        queue.reorder();
+        queue
+    }
+
+    fn reorder(&mut self) {
+        self.order = (0..self.items.len()).collect();
+        if self.shuffle {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(1);
+            shuffle(&mut self.order, seed);
+        }
+    }
+
+    pub fn current(&self) -> Option<&PlaylistItem> {
+        self.position
+            .and_then(|p| self.order.get(p))
+            .and_then(|i| self.items.get(*i))
+    }
+
+    // Moves to the next song, None once the queue is over.
+    pub fn advance(&mut self) -> Option<PlaylistItem> {
+        if self.is_finished() {
+            return None;
+        }
+
+        let next = match (self.position, self.repeat) {
+            (None, _) => 0,
+            (Some(p), RepeatMode::One) => p,
+            (Some(p), _) => p + 1,
+        };
+
+        if next >= self.order.len() {
+            if self.repeat != RepeatMode::All || self.order.is_empty() {
+                self.position = Some(self.order.len());
+                return None;
+            }
+
+            self.reorder();
+            self.position = Some(0);
+        } else {
+            self.position = Some(next);
+        }
+
+        self.current().cloned()
+    }
+
+    // Whether there is a song after the current one.
+    pub fn has_next(&self) -> bool {
+        match (self.position, self.repeat) {
+            (_, _) if self.order.is_empty() || self.is_finished() => false,
+            (None, _) | (_, RepeatMode::One | RepeatMode::All) => true,
+            (Some(p), RepeatMode::Off) => p + 1 < self.order.len(),
+        }
+    }
+
+    pub fn loaded(&mut self) {
+        self.failed = 0;
+    }
+
+    // The current song could not be loaded, a repeating queue is over once none of a whole pass
+    // could be, it would go round without end otherwise.
+    pub fn load_failed(&mut self) {
+        self.failed += 1;
+        let pass = match self.repeat {
+            RepeatMode::One => 1,
+            _ => self.order.len(),
+        };
+        if self.failed >= pass {
+            self.position = Some(self.order.len());
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position.is_some_and(|p| p >= self.order.len())
+    }
+
+    // 1 based position for display.
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.position.map_or(0, |p| (p + 1).min(self.order.len())),
+            self.order.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(repeat: RepeatMode) -> Queue {
+        let items = ["a", "b"].map(|s| PlaylistItem::Search(s.to_owned()));
+        Queue::new(items.to_vec(), false, repeat)
+    }
+
+    #[test]
+    fn a_repeating_queue_stops_once_a_whole_pass_fails() {
+        let mut all = queue(RepeatMode::All);
+        all.advance();
+        all.load_failed();
+        all.advance();
+        all.loaded();
+        all.advance();
+        all.load_failed();
+        assert!(all.has_next());
+        all.advance();
+        all.load_failed();
+        assert!(all.is_finished());
+        assert!(!all.has_next());
+        assert_eq!(all.advance(), None);
+
+        let mut one = queue(RepeatMode::One);
+        one.advance();
+        one.load_failed();
+        assert!(one.is_finished());
+
+        let mut off = queue(RepeatMode::Off);
+        off.advance();
+        assert!(off.has_next());
+        off.advance();
+        assert!(!off.has_next());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::library::Library;
+use crate::lrclib::Lrclib;
+use crate::response::Root;
+use crate::search::query_title_artist;
+use crate::{Lyrics, Musixmatch};
+use serde_derive::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderName {
+    // Songs already in the library.
+    Cache,
+    // Lyrics files in the `lyrics_dir` of the config.
+    Files,
+    Musixmatch,
+    // Any server speaking the LRCLIB api, synced lyrics included.
+    Lrclib,
+}
+
+impl ProviderName {
+    // Online providers are skipped in offline mode.
+    pub fn is_online(self) -> bool {
+        matches!(self, ProviderName::Musixmatch | ProviderName::Lrclib)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProviderName::Cache => "library",
+            ProviderName::Files => "lyrics files",
+            ProviderName::Musixmatch => "musixmatch",
+            ProviderName::Lrclib => "lrclib",
+        }
+    }
+}
+
+// The providers to ask in order, the ones left out are not used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProviderOrder(pub Vec<ProviderName>);
+
+impl Default for ProviderOrder {
+    fn default() -> Self {
+        ProviderOrder(vec![
+            ProviderName::Cache,
+            ProviderName::Files,
+            ProviderName::Musixmatch,
+            ProviderName::Lrclib,
+        ])
+    }
+}
+
+// A directory of json lyrics files named `<artist> - <title>.json`, or just `<title>.json`.
+#[derive(Debug, Clone)]
+pub struct LocalFiles {
+    pub dir: PathBuf,
+}
+
+impl Lyrics for LocalFiles {
+    type Out = Result<Root, String>;
+
+    async fn get_lyrics(&self, query: String) -> Result<Root, String> {
+        let (title, artist) = query_title_artist(&query);
+        let names = [format!("{} - {}", artist, title), title.to_owned()];
+
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| format!("Could not read {}, Error {}", self.dir.display(), e))?;
+        let path = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .find(|p| {
+                p.file_stem().is_some_and(|s| {
+                    names
+                        .iter()
+                        .any(|n| n.eq_ignore_ascii_case(&s.to_string_lossy()))
+                })
+            })
+            .ok_or_else(|| format!("{} is not in {}", title, self.dir.display()))?;
+
+        let s = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}, Error {}", path.display(), e))?;
+        serde_json::from_str(&s).map_err(|e| format!("Failed to deserialize {}", e))
+    }
+}
+
+// The lyrics and which provider of the chain had them.
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub root: Root,
+    pub provider: ProviderName,
+    // Set when the song came from the library, the others still have to be added to it.
+    pub library_id: Option<String>,
+}
+
+// The providers in the configured order, each one asked only when the ones before it had no match
+// or failed.
+pub struct ProviderChain<'a> {
+    pub order: &'a [ProviderName],
+    pub offline: bool,
+    pub library: &'a Library,
+    pub files: Option<LocalFiles>,
+    pub musixmatch: &'a Musixmatch,
+    pub lrclib: Option<&'a Lrclib>,
+}
+
+impl ProviderChain<'_> {
+    async fn ask(&self, provider: ProviderName, query: &str) -> Option<Result<Found, String>> {
+        let found = |root: Root| Found {
+            root,
+            provider,
+            library_id: None,
+        };
+
+        match provider {
+            ProviderName::Cache => {
+                let (title, artist) = query_title_artist(query);
+                let id = self.library.find(&title, &artist)?.id.to_owned();
+                Some(
+                    self.library
+                        .load_root(&id)
+                        .map(|root| Found {
+                            library_id: Some(id),
+                            ..found(root)
+                        })
+                        .map_err(|e| format!("Failed to load {}, Error {}", title, e)),
+                )
+            }
+            ProviderName::Files => {
+                let files = self.files.as_ref()?;
+                Some(files.get_lyrics(query.to_owned()).await.map(found))
+            }
+            ProviderName::Musixmatch => Some(self.musixmatch.fetch_root(query).await.map(found)),
+            ProviderName::Lrclib => {
+                let lrclib = self.lrclib?;
+                Some(lrclib.fetch_root(query).await.map(found))
+            }
+        }
+    }
+}
+
+impl Lyrics for ProviderChain<'_> {
+    type Out = Result<Found, String>;
+
+    async fn get_lyrics(&self, query: String) -> Result<Found, String> {
+        let mut errors = Vec::new();
+
+        for provider in self.order {
+            if self.offline && provider.is_online() {
+                continue;
+            }
+
+            match self.ask(*provider, &query).await {
+                Some(Ok(found)) => return Ok(found),
+                Some(Err(e)) => errors.push(format!("{}: {}", provider.name(), e)),
+                None => (),
+            }
+        }
+
+        match errors.is_empty() {
+            true => Err(format!(
+                "No enabled lyrics provider has {}",
+                query_title_artist(&query).0
+            )),
+            false => Err(errors.join(" | ")),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::difficulty::Difficulty;
+use crate::normalize::NormalizeOptions;
+use crate::response::Root;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerProgress {
+    pub id: u32,
+    pub name: String,
+    pub done: u32,
+    pub total: u32,
+    pub wpm: f64,
+    // 1 for the first player to finish.
+    pub place: Option<u32>,
+}
+
+// Every message is one line of json on the socket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RaceMessage {
+    // Client to server, the first player to join is the host.
+    Join {
+        name: String,
+    },
+    // Server to the client which just joined.
+    Welcome {
+        player_id: u32,
+        host: bool,
+    },
+    // Host to server, then server to everyone.
+    Start {
+        title: String,
+        artist: String,
+        lyrics: Root,
+        difficulty: Difficulty,
+        normalization: NormalizeOptions,
+    },
+    // Client to server.
+    Progress {
+        done: u32,
+        total: u32,
+        wpm: f64,
+        finished: bool,
+    },
+    // Server to everyone whenever a player moves.
+    Standings {
+        players: Vec<PlayerProgress>,
+    },
+}
+
+enum ServerEvent {
+    Connected(u32, UnboundedSender<RaceMessage>),
+    Message(u32, RaceMessage),
+    Disconnected(u32),
+}
+
+async fn send_line(writer: &mut (impl AsyncWriteExt + Unpin), message: &RaceMessage) -> Result<()> {
+    let mut line =
+        serde_json::to_string(message).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+// Reads lines into messages and writes the messages of the channel as lines, until either side
+// goes away.
+fn spawn_connection(
+    stream: TcpStream,
+    incoming: impl Fn(RaceMessage) -> bool + Send + 'static,
+    mut outgoing: UnboundedReceiver<RaceMessage>,
+    on_close: impl FnOnce() + Send + 'static,
+) {
+    let (reader, mut writer) = stream.into_split();
+
+    tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            if send_line(&mut writer, &message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Ok(message) = serde_json::from_str(&line) {
+                if !incoming(message) {
+                    break;
+                }
+            }
+        }
+        on_close();
+    });
+}
+
+fn standings(players: &BTreeMap<u32, PlayerProgress>) -> RaceMessage {
+    let mut players: Vec<PlayerProgress> = players.values().cloned().collect();
+    players.sort_by(|a, b| {
+        a.place
+            .unwrap_or(u32::MAX)
+            .cmp(&b.place.unwrap_or(u32::MAX))
+            .then(b.done.cmp(&a.done))
+    });
+    RaceMessage::Standings { players }
+}
+
+// Where the host joins its own server, a server listening on every interface is reached on the
+// loopback of the same family.
+pub fn host_join_addr(listening: SocketAddr) -> SocketAddr {
+    let ip = match listening.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, listening.port())
+}
+
+pub async fn run_server(listener: TcpListener) -> Result<()> {
+    let (tx, mut rx) = unbounded_channel::<ServerEvent>();
+
+    let accept_tx = tx.clone();
+    tokio::spawn(async move {
+        let mut next_id = 0;
+        while let Ok((stream, _)) = listener.accept().await {
+            let id = next_id;
+            next_id += 1;
+
+            let (out_tx, out_rx) = unbounded_channel();
+            if accept_tx.send(ServerEvent::Connected(id, out_tx)).is_err() {
+                break;
+            }
+
+            let message_tx = accept_tx.clone();
+            let close_tx = accept_tx.clone();
+            spawn_connection(
+                stream,
+                move |m| message_tx.send(ServerEvent::Message(id, m)).is_ok(),
+                out_rx,
+                move || {
+                    let _ = close_tx.send(ServerEvent::Disconnected(id));
+                },
+            );
+        }
+    });
+
+    let mut clients: BTreeMap<u32, UnboundedSender<RaceMessage>> = BTreeMap::new();
+    let mut players: BTreeMap<u32, PlayerProgress> = BTreeMap::new();
+    let mut host: Option<u32> = None;
+    let mut finished = 0;
+
+    let broadcast = |clients: &BTreeMap<u32, UnboundedSender<RaceMessage>>, m: &RaceMessage| {
+        for c in clients.values() {
+            let _ = c.send(m.to_owned());
+        }
+    };
+
+    while let Some(event) = rx.recv().await {
+        match event {
+            ServerEvent::Connected(id, out) => {
+                clients.insert(id, out);
+            }
+            ServerEvent::Disconnected(id) => {
+                clients.remove(&id);
+                players.remove(&id);
+                if host == Some(id) {
+                    host = players.keys().next().copied();
+                }
+                broadcast(&clients, &standings(&players));
+            }
+            ServerEvent::Message(id, RaceMessage::Join { name }) => {
+                let is_host = *host.get_or_insert(id) == id;
+                players.insert(
+                    id,
+                    PlayerProgress {
+                        id,
+                        name,
+                        done: 0,
+                        total: 0,
+                        wpm: 0.0,
+                        place: None,
+                    },
+                );
+
+                if let Some(c) = clients.get(&id) {
+                    let _ = c.send(RaceMessage::Welcome {
+                        player_id: id,
+                        host: is_host,
+                    });
+                }
+                broadcast(&clients, &standings(&players));
+            }
+            ServerEvent::Message(id, start @ RaceMessage::Start { .. }) if host == Some(id) => {
+                finished = 0;
+                for p in players.values_mut() {
+                    p.done = 0;
+                    p.wpm = 0.0;
+                    p.place = None;
+                }
+                broadcast(&clients, &start);
+                broadcast(&clients, &standings(&players));
+            }
+            ServerEvent::Message(
+                id,
+                RaceMessage::Progress {
+                    done,
+                    total,
+                    wpm,
+                    finished: player_finished,
+                },
+            ) => {
+                if let Some(p) = players.get_mut(&id) {
+                    p.done = done;
+                    p.total = total;
+                    p.wpm = wpm;
+                    if player_finished && p.place.is_none() {
+                        finished += 1;
+                        p.place = Some(finished);
+                    }
+                }
+                broadcast(&clients, &standings(&players));
+            }
+            // Only the server sends the rest.
+            ServerEvent::Message(_, _) => (),
+        }
+    }
+
+    Ok(())
+}
+
+// Connects to a race and joins it, messages to the server go in the sender and the ones from the
+// server come out of the receiver.
+pub async fn join_race(
+    addr: impl ToSocketAddrs,
+    name: &str,
+) -> Result<(UnboundedSender<RaceMessage>, UnboundedReceiver<RaceMessage>)> {
+    let stream = TcpStream::connect(addr).await?;
+
+    let (out_tx, out_rx) = unbounded_channel();
+    let (in_tx, in_rx) = unbounded_channel();
+
+    spawn_connection(stream, move |m| in_tx.send(m).is_ok(), out_rx, || ());
+
+    let _ = out_tx.send(RaceMessage::Join {
+        name: name.to_owned(),
+    });
+    Ok((out_tx, in_rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Root2;
+
+    async fn next_standings(rx: &mut UnboundedReceiver<RaceMessage>) -> Vec<PlayerProgress> {
+        loop {
+            if let Some(RaceMessage::Standings { players }) = rx.recv().await {
+                return players;
+            }
+        }
+    }
+
+    #[test]
+    fn the_host_joins_where_the_server_listens() {
+        let join = |addr: &str| host_join_addr(addr.parse().unwrap()).to_string();
+        assert_eq!(join("0.0.0.0:7878"), "127.0.0.1:7878");
+        assert_eq!(join("[::]:7878"), "[::1]:7878");
+        assert_eq!(join("192.168.1.20:7878"), "192.168.1.20:7878");
+        assert_eq!(join("[::1]:7878"), "[::1]:7878");
+    }
+
+    #[tokio::test]
+    async fn race_on_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(run_server(listener));
+
+        let (host_tx, mut host_rx) = join_race(addr, "host").await.unwrap();
+        assert_eq!(
+            host_rx.recv().await,
+            Some(RaceMessage::Welcome {
+                player_id: 0,
+                host: true
+            })
+        );
+
+        let (guest_tx, mut guest_rx) = join_race(addr, "guest").await.unwrap();
+        assert_eq!(
+            guest_rx.recv().await,
+            Some(RaceMessage::Welcome {
+                player_id: 1,
+                host: false
+            })
+        );
+
+        let start = RaceMessage::Start {
+            title: String::from("Paranoid"),
+            artist: String::from("Black Sabbath"),
+            lyrics: vec![Root2::line("Finished with my woman")],
+            difficulty: Difficulty::Standard,
+            normalization: NormalizeOptions::default(),
+        };
+        host_tx.send(start.to_owned()).unwrap();
+        loop {
+            if guest_rx.recv().await == Some(start.to_owned()) {
+                break;
+            }
+        }
+
+        guest_tx
+            .send(RaceMessage::Progress {
+                done: 22,
+                total: 22,
+                wpm: 80.0,
+                finished: true,
+            })
+            .unwrap();
+
+        loop {
+            let players = next_standings(&mut host_rx).await;
+            if players.first().is_some_and(|p| p.place == Some(1)) {
+                assert_eq!(players[0].name, "guest");
+                assert_eq!(players[1].place, None);
+                break;
+            }
+        }
+    }
+}
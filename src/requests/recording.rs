@@ -0,0 +1,107 @@
+use crate::difficulty::Difficulty;
+use crate::library::data_dir;
+use crate::normalize::NormalizeOptions;
+use crate::response::Root;
+use serde_derive::{Deserialize, Serialize};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedKey {
+    Char(char),
+    Backspace,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeystrokeRecord {
+    // Seconds since the start of the session.
+    pub at: f64,
+    pub key: RecordedKey,
+    pub expected: Option<char>,
+    pub correct: bool,
+    pub line_index: u32,
+    pub offset: u32,
+}
+
+// Everything needed to play a session back, the lyrics included so a recording can be shared.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub song_id: Option<String>,
+    pub title: String,
+    pub artist: String,
+    pub difficulty: Difficulty,
+    pub normalization: NormalizeOptions,
+    pub lyrics: Root,
+    pub keys: Vec<KeystrokeRecord>,
+}
+
+pub fn recordings_dir() -> PathBuf {
+    data_dir().join("recordings")
+}
+
+impl Recording {
+    pub fn new(
+        song_id: Option<String>,
+        lyrics: Root,
+        difficulty: Difficulty,
+        normalization: NormalizeOptions,
+    ) -> Self {
+        Recording {
+            song_id,
+            title: String::new(),
+            artist: String::new(),
+            difficulty,
+            normalization,
+            lyrics,
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, dir: &Path, finished_at: u64) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+
+        let name = match self.song_id.as_ref() {
+            Some(id) => format!("{}-{}.json", finished_at, id),
+            None => format!("{}.json", finished_at),
+        };
+        let path = dir.join(name);
+
+        let s = serde_json::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        std::fs::write(&path, s)?;
+        Ok(path)
+    }
+
+    // Seconds from the start to the last keystroke.
+    pub fn duration(&self) -> f64 {
+        self.keys.last().map_or(0.0, |k| k.at)
+    }
+
+    // Where the recorded session was after `at` seconds.
+    pub fn progress_at(&self, at: f64) -> GhostProgress {
+        let mut progress = GhostProgress::default();
+
+        for k in self.keys.iter().take_while(|k| k.at <= at) {
+            if k.correct {
+                progress.correct_hits += 1;
+                progress.line_index = k.line_index;
+                progress.offset = k.offset + 1;
+            }
+        }
+
+        progress
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GhostProgress {
+    pub correct_hits: u32,
+    pub line_index: u32,
+    // Character of the line the ghost is about to type.
+    pub offset: u32,
+}
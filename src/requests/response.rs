@@ -1,3 +1,4 @@
+use crate::normalize::{normalize_root, NormalizeOptions};
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 
@@ -9,6 +10,16 @@ pub struct Root2 {
     pub time: Time,
 }
 
+impl Root2 {
+    // A line without timing, the way text which is not from a provider comes in.
+    pub fn line(text: &str) -> Self {
+        Root2 {
+            text: text.to_owned(),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Time {
     pub total: f64,
@@ -17,6 +28,20 @@ pub struct Time {
     pub hundredths: i64,
 }
 
+// Lines without timing as lyrics, blank ones included.
+pub fn root_from_lines(lines: &[&str]) -> Root {
+    lines.iter().map(|l| Root2::line(l)).collect()
+}
+
+// Plain text as lyrics, one line each and without the blank lines the song would skip anyway.
+pub fn root_from_text(text: &str) -> Root {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(Root2::line)
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SongStatus {
     Completed,
@@ -28,6 +53,7 @@ pub struct Song {
     pub song: Option<Root>,
     pub sentence: String,
     pub line_index: u32,
+    pub normalization: NormalizeOptions,
 
     cur_loc: u32,
     song_status: SongStatus,
@@ -43,15 +69,63 @@ impl Song {
     }
 
     pub fn new(s: Root) -> Self {
+        Song::with_options(s, &NormalizeOptions::default())
+    }
+
+    // Normalization can leave lines empty, the song starts at the first line with some text.
+    pub fn with_options(s: Root, options: &NormalizeOptions) -> Self {
+        let s = normalize_root(&s, options);
+        let first = s.iter().position(|r| !r.text.is_empty());
+
         Song {
-            song: Some(s.to_owned()),
-            sentence: s.first().unwrap().text.to_owned(),
-            line_index: 0,
+            sentence: first.map(|i| s[i].text.to_owned()).unwrap_or_default(),
+            line_index: first.unwrap_or(0) as u32,
+            normalization: options.to_owned(),
             cur_loc: 0,
-            song_status: SongStatus::Continuing,
+            song_status: match first {
+                Some(_) => SongStatus::Continuing,
+                None => SongStatus::Completed,
+            },
+            song: Some(s),
         }
     }
 
+    // Characters typed so far and characters in the whole song.
+    pub fn get_progress(&self) -> (u32, u32) {
+        let lines = match self.song.as_ref() {
+            Some(s) => s,
+            None => return (0, 0),
+        };
+
+        let total: usize = lines.iter().map(|l| l.text.chars().count()).sum();
+        if self.song_status == SongStatus::Completed {
+            return (total as u32, total as u32);
+        }
+
+        let before: usize = lines
+            .iter()
+            .take(self.line_index as usize)
+            .map(|l| l.text.chars().count())
+            .sum();
+
+        (before as u32 + self.cur_loc, total as u32)
+    }
+
+    // Lines finished so far and lines in the whole song.
+    pub fn get_line_progress(&self) -> (u32, u32) {
+        let total = self.song.as_ref().map_or(0, |s| s.len() as u32);
+        if self.song_status == SongStatus::Completed {
+            return (total, total);
+        }
+
+        (self.line_index.min(total), total)
+    }
+
+    // Characters typed and characters in the current line.
+    pub fn get_line_char_progress(&self) -> (u32, u32) {
+        (self.cur_loc, self.sentence.chars().count() as u32)
+    }
+
     pub fn get_current_char(&self) -> Option<char> {
         self.sentence.chars().nth(self.cur_loc as usize)
     }
@@ -60,8 +134,34 @@ impl Song {
         Some(self.song_status.to_owned())
     }
 
+    // More lines at the end, normalized like the others. A completed song goes on with them.
+    pub fn append(&mut self, root: &Root) {
+        let lines = normalize_root(root, &self.normalization);
+        let song = self.song.get_or_insert_with(Root::new);
+        let from = song.len();
+        song.extend(lines);
+
+        let next = song[from..].iter().position(|r| !r.text.is_empty());
+        if let (SongStatus::Completed, Some(i)) = (&self.song_status, next) {
+            self.jump_to((from + i) as u32);
+        }
+    }
+
+    // Back to the start of a line with some text, to type it again.
+    pub fn jump_to(&mut self, line_index: u32) {
+        let text = match self.song.as_ref().and_then(|s| s.get(line_index as usize)) {
+            Some(lyric) if !lyric.text.is_empty() => lyric.text.to_owned(),
+            _ => return,
+        };
+
+        self.line_index = line_index;
+        self.sentence = text;
+        self.cur_loc = 0;
+        self.song_status = SongStatus::Continuing;
+    }
+
     pub fn update_sentence(&mut self) {
-        if self.song == None {
+        if self.song.is_none() {
             return;
         }
 
@@ -69,13 +169,14 @@ impl Song {
 
         self.cur_loc += 1;
 
-        if (self.cur_loc as usize) >= self.sentence.len() {
+        // Counted in characters, lyrics out of ascii take more than a byte each.
+        if (self.cur_loc as usize) >= self.sentence.chars().count() {
             self.line_index += 1;
 
             loop {
                 match local_song.get(self.line_index as usize) {
                     Some(lyric) => {
-                        if lyric.text.len() != 0 {
+                        if !lyric.text.is_empty() {
                             self.cur_loc = 0;
                             self.sentence = lyric.text.to_owned();
                             break;
@@ -0,0 +1,163 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestPolicy {
+    // Tries of one lookup, the first one included.
+    pub attempts: u32,
+    // Wait before the first retry, doubled on every retry after it.
+    pub base_delay_ms: u64,
+    // Longer waits, Retry-After included, give up instead.
+    pub max_delay_ms: u64,
+    // Requests sent in any minute, 0 for no limit.
+    pub requests_per_minute: u32,
+    // A response taking longer fails the attempt, which is then retried, 0 waits for as long as it takes.
+    pub timeout_ms: u64,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 8000,
+            requests_per_minute: 30,
+            timeout_ms: 10000,
+        }
+    }
+}
+
+impl RequestPolicy {
+    // Exponential backoff with up to half of it added as jitter, so clients do not retry in step.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay_ms);
+        let jitter = match delay / 2 {
+            0 => 0,
+            half => RandomState::new().build_hasher().finish() % (half + 1),
+        };
+
+        Duration::from_millis((delay + jitter).min(self.max_delay_ms))
+    }
+
+    pub fn max_delay(&self) -> Duration {
+        Duration::from_millis(self.max_delay_ms)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        match self.timeout_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+}
+
+// Retry-After in seconds, the http date form is not used by RapidAPI.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+// What is left of the RapidAPI plan, from the headers of the last response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Quota {
+    pub remaining: u64,
+    pub limit: Option<u64>,
+}
+
+impl Quota {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        Some(Quota {
+            remaining: number("x-ratelimit-requests-remaining")?,
+            limit: number("x-ratelimit-requests-limit"),
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        match self.limit {
+            Some(limit) => format!("{}/{} requests left", self.remaining, limit),
+            None => format!("{} requests left", self.remaining),
+        }
+    }
+}
+
+// Requests sent in the last minute, to stay under the plan without waiting for a 429.
+#[derive(Debug, Clone, Default)]
+pub struct RequestBudget {
+    sent: VecDeque<Instant>,
+}
+
+impl RequestBudget {
+    // Takes one request from the budget, or says how long until one is free.
+    pub fn take(&mut self, per_minute: u32, now: Instant) -> Result<(), Duration> {
+        let minute = Duration::from_secs(60);
+        while self
+            .sent
+            .front()
+            .is_some_and(|s| now.duration_since(*s) >= minute)
+        {
+            self.sent.pop_front();
+        }
+
+        if per_minute > 0 && self.sent.len() >= per_minute as usize {
+            let oldest = self.sent.front().copied().unwrap_or(now);
+            return Err(minute.saturating_sub(now.duration_since(oldest)));
+        }
+
+        self.sent.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_stays_capped() {
+        let policy = RequestPolicy::default();
+        for retry in 0..8 {
+            let delay = policy.backoff(retry).as_millis() as u64;
+            let base = (500 << retry).min(8000);
+            assert!(
+                delay >= base && delay <= (base * 3 / 2).min(8000),
+                "{}",
+                delay
+            );
+        }
+    }
+
+    #[test]
+    fn budget_frees_up_after_a_minute() {
+        let mut budget = RequestBudget::default();
+        let start = Instant::now();
+
+        assert!(budget.take(2, start).is_ok());
+        assert!(budget.take(2, start + Duration::from_secs(10)).is_ok());
+        assert_eq!(
+            budget.take(2, start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+        assert!(budget.take(2, start + Duration::from_secs(60)).is_ok());
+    }
+}
@@ -0,0 +1,138 @@
+use crate::provider::ProviderName;
+use crate::response::Root;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CandidateSource {
+    Provider(ProviderName, Root),
+    Local { id: String, path: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub title: String,
+    pub artist: String,
+    pub album: Option<String>,
+    pub duration: Option<f64>,
+    pub synced: bool,
+    pub source: CandidateSource,
+}
+
+impl Candidate {
+    // The providers only answer with the best match, so the title and artist are the ones the
+    // user asked for.
+    pub fn from_provider(query: &str, root: Root, provider: ProviderName) -> Self {
+        let (title, artist) = query_title_artist(query);
+
+        Candidate {
+            title,
+            artist,
+            album: None,
+            duration: root.last().map(|r| r.time.total).filter(|d| *d > 0.0),
+            synced: root.iter().any(|r| r.time.total > 0.0),
+            source: CandidateSource::Provider(provider, root),
+        }
+    }
+
+    pub fn get_root(&self) -> Option<Root> {
+        match &self.source {
+            CandidateSource::Provider(_, root) => Some(root.to_owned()),
+            CandidateSource::Local { path, .. } => std::fs::read_to_string(path)
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok()),
+        }
+    }
+
+    pub fn library_id(&self) -> Option<&str> {
+        match &self.source {
+            CandidateSource::Provider(..) => None,
+            CandidateSource::Local { id, .. } => Some(id.as_str()),
+        }
+    }
+
+    pub fn haystack(&self) -> String {
+        match &self.album {
+            Some(album) => format!("{} {} {}", self.title, self.artist, album),
+            None => format!("{} {}", self.title, self.artist),
+        }
+    }
+
+    pub fn format_duration(&self) -> String {
+        match self.duration {
+            Some(d) => format!("{}:{:02}", (d as u64) / 60, (d as u64) % 60),
+            None => String::from("--:--"),
+        }
+    }
+}
+
+// Splits `t: <title>, a: <artist>` into its key value pairs.
+pub fn parse_query(query: &str) -> Vec<(&str, &str)> {
+    query
+        .split(',')
+        .filter_map(|s| s.split_once(':'))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect()
+}
+
+pub fn query_title_artist(query: &str) -> (String, String) {
+    let mut title = String::new();
+    let mut artist = String::new();
+
+    for (key, val) in parse_query(query) {
+        if key.eq_ignore_ascii_case("t") {
+            title = val.to_owned();
+        } else if key.eq_ignore_ascii_case("a") {
+            artist = val.to_owned();
+        }
+    }
+
+    (title, artist)
+}
+
+// Subsequence match, consecutive and word start hits score higher. None when the pattern does
+// not match at all.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let mut score: i64 = 0;
+    let mut p_index = 0;
+    let mut last_match: Option<usize> = None;
+    let mut prev_char = ' ';
+
+    for (t_index, t_char) in text.to_lowercase().chars().enumerate() {
+        if p_index < pattern.len() && t_char == pattern[p_index] {
+            score += 1;
+            if last_match.is_some_and(|l| l + 1 == t_index) {
+                score += 5;
+            }
+            if !prev_char.is_alphanumeric() {
+                score += 3;
+            }
+
+            last_match = Some(t_index);
+            p_index += 1;
+        }
+        prev_char = t_char;
+    }
+
+    if p_index == pattern.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+// Returns the indices of the matching candidates, best match first.
+pub fn filter_candidates(candidates: &[Candidate], pattern: &str) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| fuzzy_score(pattern, &c.haystack()).map(|s| (i, s)))
+        .collect();
+
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
@@ -0,0 +1,335 @@
+use crate::difficulty::Difficulty;
+use crate::normalize::NormalizeOptions;
+use crate::recording::{KeystrokeRecord, RecordedKey};
+use crate::response::{Root, Song, SongStatus};
+use crate::stats::SessionStats;
+use std::collections::BTreeSet;
+use std::time::Instant;
+
+// One typing session over a song, without anything of the terminal so any front end or test can
+// drive it with plain characters.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub song: Song,
+    pub difficulty: Difficulty,
+    pub correct_hits: u32,
+    pub total_hits: u32,
+    // Mistakes still to delete with backspace in strict mode.
+    pub uncorrected_errors: u32,
+    // Line index and offset of every character mistyped at least once.
+    pub mistakes: BTreeSet<(u32, u32)>,
+    // Speed of the session clock, a replay at twice the speed runs it at 2.0.
+    pub time_scale: f64,
+    // The line typed over and over until the loop is turned off.
+    pub loop_line: Option<u32>,
+    // Times a looped line was typed to the end.
+    pub loops: u32,
+    // Seconds on the session clock a timed test ends at, the song does not end it.
+    pub time_limit: Option<f64>,
+
+    stats: SessionStats,
+    // None until the first key of a timed session, its clock starts there.
+    started_at: Option<Instant>,
+}
+
+impl Session {
+    pub fn new(root: Root, difficulty: Difficulty, normalization: &NormalizeOptions) -> Self {
+        let mut session = Session {
+            song: Song::with_options(root, normalization),
+            difficulty,
+            correct_hits: 0,
+            total_hits: 0,
+            uncorrected_errors: 0,
+            mistakes: BTreeSet::new(),
+            time_scale: 1.0,
+            loop_line: None,
+            loops: 0,
+            time_limit: None,
+            stats: SessionStats::default(),
+            started_at: Some(Instant::now()),
+        };
+        session.skip_ignored_chars();
+        session
+    }
+
+    // Seconds of typing so far on the session clock, which stops at the time limit.
+    pub fn elapsed(&self) -> f64 {
+        let elapsed = self
+            .started_at
+            .map_or(0.0, |s| s.elapsed().as_secs_f64() * self.time_scale);
+        self.time_limit.map_or(elapsed, |limit| elapsed.min(limit))
+    }
+
+    // Ends the session after the seconds, counted from the first key instead of now.
+    pub fn set_time_limit(&mut self, seconds: f64) {
+        self.time_limit = Some(seconds);
+        self.started_at = None;
+    }
+
+    pub fn time_left(&self) -> Option<f64> {
+        self.time_limit.map(|limit| limit - self.elapsed())
+    }
+
+    fn is_time_up_at(&self, at: f64) -> bool {
+        self.time_limit.is_some_and(|limit| at >= limit)
+    }
+
+    // True once the time limit is reached, the word and line in progress are closed then.
+    pub fn time_up(&mut self) -> bool {
+        if !self.is_time_up_at(self.elapsed()) {
+            return false;
+        }
+
+        self.stats.finish();
+        true
+    }
+
+    // More lines to type, for a timed test which would otherwise run out of them.
+    pub fn extend(&mut self, root: &Root) {
+        self.song.append(root);
+        self.skip_ignored_chars();
+    }
+
+    pub fn status(&self) -> SongStatus {
+        if self.is_time_up_at(self.elapsed()) {
+            return SongStatus::Completed;
+        }
+
+        self.song
+            .get_current_status()
+            .unwrap_or(SongStatus::Completed)
+    }
+
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
+    // Words per minute, a word being five correct hits.
+    pub fn wpm(&self) -> f64 {
+        self.wpm_at(self.elapsed())
+    }
+
+    pub fn wpm_at(&self, at: f64) -> f64 {
+        let minutes = at / 60.0;
+        if minutes <= 0.0 {
+            return 0.0;
+        }
+
+        (self.correct_hits as f64 / 5.0) / minutes
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.total_hits == 0 {
+            return 0.0;
+        }
+
+        self.correct_hits as f64 / self.total_hits as f64 * 100.0
+    }
+
+    // Loops the current line, or lets the song go on when it already loops one.
+    pub fn toggle_loop(&mut self) -> Option<u32> {
+        self.loop_line = match self.loop_line {
+            Some(_) => None,
+            None if self.status() == SongStatus::Continuing => Some(self.song.line_index),
+            None => None,
+        };
+        self.loop_line
+    }
+
+    pub fn feed(&mut self, c: char) -> Option<KeystrokeRecord> {
+        self.press(RecordedKey::Char(c))
+    }
+
+    pub fn backspace(&mut self) -> Option<KeystrokeRecord> {
+        self.press(RecordedKey::Backspace)
+    }
+
+    pub fn press(&mut self, key: RecordedKey) -> Option<KeystrokeRecord> {
+        self.started_at.get_or_insert_with(Instant::now);
+        self.press_at(key, self.elapsed())
+    }
+
+    // Types a key `at` seconds into the session, nothing happens once the song or the time is over.
+    pub fn press_at(&mut self, key: RecordedKey, at: f64) -> Option<KeystrokeRecord> {
+        if self.is_time_up_at(at) {
+            return None;
+        }
+        let expected = self.song.get_current_char()?;
+        let record = |correct: bool, song: &Song| KeystrokeRecord {
+            at,
+            key,
+            expected: Some(expected),
+            correct,
+            line_index: song.line_index,
+            offset: song.get_current_location(),
+        };
+
+        // Backspace is the correction in strict mode, in the other modes it is a miss.
+        if key == RecordedKey::Backspace && self.difficulty.must_correct() {
+            self.uncorrected_errors = self.uncorrected_errors.saturating_sub(1);
+            return Some(record(false, &self.song));
+        }
+
+        self.total_hits += 1;
+        let correct = self.uncorrected_errors == 0
+            && matches!(key, RecordedKey::Char(t) if self.difficulty.matches(expected, t));
+        let keystroke = record(correct, &self.song);
+        if key != RecordedKey::Backspace {
+            self.stats
+                .record(at, expected, self.song.line_index, correct);
+        }
+
+        if correct {
+            self.correct_hits += 1;
+            self.song.update_sentence();
+            self.skip_ignored_chars();
+
+            let left_the_loop = self.song.line_index != self.loop_line.unwrap_or_default()
+                || self.status() == SongStatus::Completed;
+            if let Some(line) = self.loop_line.filter(|_| left_the_loop) {
+                self.song.jump_to(line);
+                self.skip_ignored_chars();
+                self.loops += 1;
+            }
+
+            if self.status() == SongStatus::Completed {
+                self.stats.finish();
+            }
+        } else {
+            if self.difficulty.must_correct() {
+                self.uncorrected_errors += 1;
+            }
+            self.mistakes
+                .insert((self.song.line_index, self.song.get_current_location()));
+        }
+
+        Some(keystroke)
+    }
+
+    // Moves the cursor over the characters the difficulty does not ask for.
+    fn skip_ignored_chars(&mut self) {
+        while self
+            .song
+            .get_current_char()
+            .is_some_and(|c| self.difficulty.skips(c))
+        {
+            self.song.update_sentence();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::root_from_lines;
+
+    fn session(lines: &[&str], difficulty: Difficulty) -> Session {
+        Session::new(
+            root_from_lines(lines),
+            difficulty,
+            &NormalizeOptions::default(),
+        )
+    }
+
+    #[test]
+    fn feeds_a_song_to_the_end() {
+        let mut s = session(&["Hi yo", "", "ok"], Difficulty::Standard);
+        for c in "Hi xyo".chars() {
+            s.feed(c);
+        }
+        assert_eq!(s.song.line_index, 2);
+        assert_eq!(s.status(), SongStatus::Continuing);
+
+        s.feed('o');
+        s.feed('k');
+        assert_eq!(s.status(), SongStatus::Completed);
+        assert_eq!((s.correct_hits, s.total_hits), (7, 8));
+        assert_eq!(s.stats().most_missed(1), vec![('y', 1)]);
+        assert_eq!(s.mistakes, BTreeSet::from([(0, 3)]));
+        assert_eq!(s.feed('x'), None);
+    }
+
+    #[test]
+    fn strict_backspace_clears_mistakes() {
+        let mut s = session(&["abc"], Difficulty::Strict);
+        s.feed('a');
+        s.feed('x');
+        assert_eq!(s.uncorrected_errors, 1);
+
+        let backspace = s.backspace().unwrap();
+        assert!(!backspace.correct);
+        assert_eq!(s.uncorrected_errors, 0);
+        assert_eq!(s.total_hits, 2);
+
+        s.feed('b');
+        s.feed('c');
+        assert_eq!(s.status(), SongStatus::Completed);
+    }
+
+    #[test]
+    fn keystrokes_report_where_they_were_typed() {
+        let mut s = session(&["Hey, you"], Difficulty::Relaxed);
+        for c in "hey".chars() {
+            s.feed(c);
+        }
+
+        // The comma is skipped over in relaxed mode.
+        let k = s.press_at(RecordedKey::Char(' '), 1.5).unwrap();
+        assert_eq!((k.at, k.offset, k.expected), (1.5, 4, Some(' ')));
+        assert!(k.correct);
+        assert_eq!(s.wpm_at(6.0), 8.0);
+    }
+
+    #[test]
+    fn the_clock_ends_a_timed_session() {
+        let mut s = session(&["ab"], Difficulty::Standard);
+        s.time_limit = Some(10.0);
+        s.press_at(RecordedKey::Char('a'), 1.0);
+        s.press_at(RecordedKey::Char('b'), 2.0);
+
+        // Out of lines before the time is up, more are streamed in.
+        assert_eq!(s.song.get_current_status(), Some(SongStatus::Completed));
+        s.extend(&root_from_lines(&["cd"]));
+        assert_eq!((s.song.line_index, s.status()), (1, SongStatus::Continuing));
+        assert!(s.press_at(RecordedKey::Char('c'), 9.5).is_some());
+        assert_eq!(s.press_at(RecordedKey::Char('d'), 10.0), None);
+        assert_eq!(s.correct_hits, 3);
+        assert_eq!(s.wpm_at(10.0), 3.6);
+    }
+
+    #[test]
+    fn a_looped_line_starts_over_until_the_loop_is_off() {
+        let mut s = session(&["ab", "", "cd"], Difficulty::Standard);
+        assert_eq!(s.toggle_loop(), Some(0));
+        for c in "abab".chars() {
+            s.feed(c);
+        }
+        assert_eq!((s.song.line_index, s.loops), (0, 2));
+
+        assert_eq!(s.toggle_loop(), None);
+        for c in "abcd".chars() {
+            s.feed(c);
+        }
+        assert_eq!(s.status(), SongStatus::Completed);
+
+        // The last line loops too instead of finishing the song.
+        let mut s = session(&["ab"], Difficulty::Standard);
+        s.toggle_loop();
+        s.feed('a');
+        s.feed('b');
+        assert_eq!((s.status(), s.loops), (SongStatus::Continuing, 1));
+    }
+
+    #[test]
+    fn a_timed_clock_waits_for_the_first_key() {
+        let mut s = session(&["ab"], Difficulty::Standard);
+        s.set_time_limit(10.0);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!((s.elapsed(), s.time_left()), (0.0, Some(10.0)));
+
+        let first = s.feed('a').unwrap();
+        assert!(first.at < 0.02);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(s.elapsed() >= 0.02);
+    }
+}
@@ -0,0 +1,169 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hit {
+    // Seconds since the start of the session.
+    pub at: f64,
+    pub expected: char,
+    pub correct: bool,
+}
+
+// Correct and missed hits of one key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyStats {
+    pub hits: u32,
+    pub misses: u32,
+}
+
+impl KeyStats {
+    pub fn add(&mut self, other: KeyStats) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64 * 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub hits: Vec<Hit>,
+    // Missed hits by the character which should have been typed.
+    pub missed_keys: BTreeMap<char, u32>,
+    // Word and the seconds it took, from the first to the last character.
+    pub word_times: Vec<(String, f64)>,
+    // Line index and the seconds spent on it.
+    pub line_times: Vec<(u32, f64)>,
+
+    current_word: String,
+    word_started: Option<f64>,
+    line_index: Option<u32>,
+    line_started: f64,
+    last_correct_at: f64,
+}
+
+impl SessionStats {
+    pub fn record(&mut self, at: f64, expected: char, line_index: u32, correct: bool) {
+        self.hits.push(Hit {
+            at,
+            expected,
+            correct,
+        });
+
+        if !correct {
+            *self.missed_keys.entry(expected).or_insert(0) += 1;
+            return;
+        }
+
+        match self.line_index {
+            None => {
+                self.line_index = Some(line_index);
+                self.line_started = 0.0;
+            }
+            Some(l) if l != line_index => {
+                self.finish_word();
+                self.line_times
+                    .push((l, self.last_correct_at - self.line_started));
+                self.line_index = Some(line_index);
+                self.line_started = self.last_correct_at;
+            }
+            _ => (),
+        }
+
+        if expected.is_whitespace() {
+            self.finish_word();
+        } else {
+            if self.word_started.is_none() {
+                self.word_started = Some(self.last_correct_at);
+            }
+            self.current_word.push(expected);
+        }
+
+        self.last_correct_at = at;
+    }
+
+    fn finish_word(&mut self) {
+        if let Some(started) = self.word_started.take() {
+            let word = std::mem::take(&mut self.current_word);
+            self.word_times.push((word, self.last_correct_at - started));
+        }
+    }
+
+    // Closes the word and line in progress, called once the song is over.
+    pub fn finish(&mut self) {
+        self.finish_word();
+        if let Some(l) = self.line_index.take() {
+            self.line_times
+                .push((l, self.last_correct_at - self.line_started));
+        }
+    }
+
+    // Words per minute of every `bucket` seconds of the session.
+    pub fn wpm_series(&self, bucket: f64) -> Vec<f64> {
+        let end = self.hits.last().map_or(0.0, |h| h.at);
+        let buckets = (end / bucket).ceil().max(1.0) as usize;
+        let mut series = vec![0.0; buckets];
+
+        for hit in self.hits.iter().filter(|h| h.correct) {
+            let index = ((hit.at / bucket) as usize).min(buckets - 1);
+            series[index] += 1.0;
+        }
+
+        series
+            .iter()
+            .map(|hits| (hits / 5.0) / (bucket / 60.0))
+            .collect()
+    }
+
+    // 100 means the same speed the whole way through, lower means more uneven.
+    pub fn consistency(&self, bucket: f64) -> f64 {
+        let series = self.wpm_series(bucket);
+        let mean = series.iter().sum::<f64>() / series.len() as f64;
+        if mean <= 0.0 {
+            return 0.0;
+        }
+
+        let variance = series.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / series.len() as f64;
+        (100.0 - variance.sqrt() / mean * 100.0).clamp(0.0, 100.0)
+    }
+
+    // Words with the most seconds per character first.
+    pub fn slowest_words(&self, count: usize) -> Vec<(String, f64)> {
+        let mut words: Vec<(String, f64)> = self
+            .word_times
+            .iter()
+            .filter(|(w, _)| !w.is_empty())
+            .cloned()
+            .collect();
+
+        words.sort_by(|a, b| {
+            (b.1 / b.0.chars().count() as f64).total_cmp(&(a.1 / a.0.chars().count() as f64))
+        });
+        words.truncate(count);
+        words
+    }
+
+    pub fn per_key(&self) -> BTreeMap<char, KeyStats> {
+        let mut keys: BTreeMap<char, KeyStats> = BTreeMap::new();
+        for hit in self.hits.iter() {
+            let key = keys.entry(hit.expected).or_default();
+            match hit.correct {
+                true => key.hits += 1,
+                false => key.misses += 1,
+            }
+        }
+        keys
+    }
+
+    pub fn most_missed(&self, count: usize) -> Vec<(char, u32)> {
+        let mut missed: Vec<(char, u32)> = self.missed_keys.iter().map(|(c, n)| (*c, *n)).collect();
+        missed.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        missed.truncate(count);
+        missed
+    }
+}
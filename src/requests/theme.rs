@@ -0,0 +1,187 @@
+use ratatui::style::Color;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    // Blue and orange instead of green and red.
+    Deuteranopia,
+    // Blue and yellow, red reads as almost black.
+    Protanopia,
+}
+
+impl ThemeName {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "dark" => Some(ThemeName::Dark),
+            "light" => Some(ThemeName::Light),
+            "high_contrast" => Some(ThemeName::HighContrast),
+            "deuteranopia" => Some(ThemeName::Deuteranopia),
+            "protanopia" => Some(ThemeName::Protanopia),
+            _ => None,
+        }
+    }
+}
+
+// Colors are written as names like "light-green" or as "#rrggbb".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    // Lyrics already typed.
+    #[serde(with = "color")]
+    pub correct: Color,
+    #[serde(with = "color")]
+    pub incorrect: Color,
+    // Lyrics still to type.
+    #[serde(with = "color")]
+    pub pending: Color,
+    // The caret, and the emphasis of the word being typed.
+    #[serde(with = "color")]
+    pub caret: Color,
+    // Background of the ghost cursor.
+    #[serde(with = "color")]
+    pub ghost: Color,
+    #[serde(with = "color")]
+    pub key_correct: Color,
+    #[serde(with = "color")]
+    pub key_incorrect: Color,
+    #[serde(with = "color")]
+    pub border: Color,
+    #[serde(with = "color")]
+    pub title: Color,
+    // Selected row of the picker and the library.
+    #[serde(with = "color")]
+    pub selection_fg: Color,
+    #[serde(with = "color")]
+    pub selection_bg: Color,
+    // Other players in a race.
+    #[serde(with = "color")]
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::bundled(ThemeName::Dark)
+    }
+}
+
+impl Theme {
+    pub fn bundled(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Theme {
+                correct: Color::Green,
+                incorrect: Color::Red,
+                pending: Color::White,
+                caret: Color::Yellow,
+                ghost: Color::Magenta,
+                key_correct: Color::Green,
+                key_incorrect: Color::Red,
+                border: Color::Reset,
+                title: Color::Reset,
+                selection_fg: Color::Black,
+                selection_bg: Color::White,
+                accent: Color::Cyan,
+            },
+            ThemeName::Light => Theme {
+                correct: Color::Rgb(0, 110, 40),
+                incorrect: Color::Rgb(190, 0, 0),
+                pending: Color::Black,
+                caret: Color::Blue,
+                ghost: Color::LightMagenta,
+                key_correct: Color::Rgb(0, 110, 40),
+                key_incorrect: Color::Rgb(190, 0, 0),
+                border: Color::DarkGray,
+                title: Color::Black,
+                selection_fg: Color::White,
+                selection_bg: Color::Black,
+                accent: Color::Blue,
+            },
+            ThemeName::HighContrast => Theme {
+                correct: Color::LightGreen,
+                incorrect: Color::LightRed,
+                pending: Color::White,
+                caret: Color::LightCyan,
+                ghost: Color::Yellow,
+                key_correct: Color::LightGreen,
+                key_incorrect: Color::LightRed,
+                border: Color::White,
+                title: Color::Yellow,
+                selection_fg: Color::Black,
+                selection_bg: Color::Yellow,
+                accent: Color::LightCyan,
+            },
+            // Okabe-Ito colors, told apart by both kinds of red-green colorblindness.
+            ThemeName::Deuteranopia => Theme {
+                correct: Color::Rgb(0, 114, 178),
+                incorrect: Color::Rgb(230, 159, 0),
+                pending: Color::White,
+                caret: Color::Rgb(240, 228, 66),
+                ghost: Color::Rgb(204, 121, 167),
+                key_correct: Color::Rgb(0, 114, 178),
+                key_incorrect: Color::Rgb(230, 159, 0),
+                border: Color::Reset,
+                title: Color::Reset,
+                selection_fg: Color::Black,
+                selection_bg: Color::White,
+                accent: Color::Rgb(86, 180, 233),
+            },
+            ThemeName::Protanopia => Theme {
+                correct: Color::Rgb(86, 180, 233),
+                incorrect: Color::Rgb(240, 228, 66),
+                pending: Color::White,
+                caret: Color::Rgb(0, 158, 115),
+                ghost: Color::Rgb(204, 121, 167),
+                key_correct: Color::Rgb(86, 180, 233),
+                key_incorrect: Color::Rgb(240, 228, 66),
+                border: Color::Reset,
+                title: Color::Reset,
+                selection_fg: Color::Black,
+                selection_bg: Color::White,
+                accent: Color::Rgb(0, 114, 178),
+            },
+        }
+    }
+}
+
+// The config takes either the name of a bundled theme or a table of colors, missing colors come
+// from the dark theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeSetting {
+    Bundled(ThemeName),
+    Custom(Theme),
+}
+
+impl Default for ThemeSetting {
+    fn default() -> Self {
+        ThemeSetting::Bundled(ThemeName::default())
+    }
+}
+
+impl ThemeSetting {
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeSetting::Bundled(name) => Theme::bundled(name),
+            ThemeSetting::Custom(theme) => theme,
+        }
+    }
+}
+
+mod color {
+    use ratatui::style::Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&color.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Color::from_str(&name).map_err(|_| D::Error::custom(format!("unknown color {}", name)))
+    }
+}
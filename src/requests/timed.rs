@@ -0,0 +1,101 @@
+use crate::library::Library;
+use crate::playlist::shuffle;
+use crate::response::Root;
+
+// The lengths a timed test can run for, in seconds.
+pub const DURATIONS: [u32; 4] = [15, 30, 60, 120];
+
+// A test against the clock, the lines of the library songs come one song after the other in a
+// shuffled order until the time is up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedTest {
+    pub seconds: u32,
+
+    ids: Vec<String>,
+    next: usize,
+}
+
+impl TimedTest {
+    // `30` or `30s`, one of the `DURATIONS`.
+    pub fn duration_from_name(name: &str) -> Option<u32> {
+        let name = name.trim();
+        let seconds = name.strip_suffix('s').unwrap_or(name).parse().ok()?;
+        DURATIONS.contains(&seconds).then_some(seconds)
+    }
+
+    pub fn new(seconds: u32, library: &Library, seed: u64) -> Self {
+        let mut ids: Vec<String> = library.entries.iter().map(|e| e.id.to_owned()).collect();
+        ids.sort();
+        shuffle(&mut ids, seed);
+
+        TimedTest {
+            seconds,
+            ids,
+            next: 0,
+        }
+    }
+
+    // The lines of the next song, starting over once every song was used. Songs which fail to load
+    // are skipped, none when no song of the library loads.
+    pub fn next_lines(&mut self, library: &Library) -> Option<Root> {
+        for _ in 0..self.ids.len() {
+            let id = &self.ids[self.next % self.ids.len()];
+            self.next = (self.next + 1) % self.ids.len();
+
+            match library.load_root(id) {
+                Ok(root) if root.iter().any(|l| !l.text.trim().is_empty()) => return Some(root),
+                _ => continue,
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::EntryOrigin;
+    use crate::response::Root2;
+
+    #[test]
+    fn only_the_listed_durations_are_taken() {
+        assert_eq!(TimedTest::duration_from_name("30"), Some(30));
+        assert_eq!(TimedTest::duration_from_name("120s"), Some(120));
+        assert_eq!(TimedTest::duration_from_name("45"), None);
+        assert_eq!(TimedTest::duration_from_name("a minute"), None);
+    }
+
+    #[test]
+    fn songs_come_back_around_and_empty_ones_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("lyricist-timed-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut library = Library::empty(dir.to_owned());
+
+        let line = Root2::line;
+        library
+            .add("One", "A", None, &vec![line("first")], EntryOrigin::Cache)
+            .unwrap();
+        library
+            .add("Two", "B", None, &vec![line("second")], EntryOrigin::Cache)
+            .unwrap();
+        library
+            .add("Silent", "C", None, &vec![line(" ")], EntryOrigin::Cache)
+            .unwrap();
+
+        let mut test = TimedTest::new(30, &library, 7);
+        let mut texts: Vec<String> = (0..4)
+            .map(|_| test.next_lines(&library).unwrap()[0].text.to_owned())
+            .collect();
+        assert_eq!(texts[0], texts[2]);
+        assert_eq!(texts[1], texts[3]);
+        texts.truncate(2);
+        texts.sort();
+        assert_eq!(texts, vec!["first", "second"]);
+
+        let mut empty = TimedTest::new(30, &Library::empty(dir.join("none")), 7);
+        assert_eq!(empty.next_lines(&library), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
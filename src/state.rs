@@ -1,46 +1,235 @@
 use std::char;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use crate::constants::QUEUE_ADVANCE_DELAY_SECS;
 use crate::keyboard_event::{KeyboardActions, KeyboardEvent, States};
+use crate::library_view::{LibraryAction, LibraryView};
+use crate::onboarding::{sample_lesson, Onboarding, OnboardingAction};
+use crate::picker::{Picker, PickerAction};
+use crate::race::RaceState;
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use libreq::response::{Song, SongStatus};
+use crossterm::event::{KeyCode, KeyModifiers};
+use libreq::config::{config_path, Config, GhostMode};
+use libreq::daily::{daily_path, Daily, DailyLog, DailyScore};
+use libreq::excerpt::{Excerpt, DEFAULT_WPM};
+use libreq::export::{export, exports_dir, format_date, DateRange, ExportFormat};
+use libreq::history::{
+    append_result, history_path, load_history, personal_best, recent_wpm, reports_dir, save_report,
+    SessionReport, SessionResult,
+};
+use libreq::library::unix_now;
+use libreq::library::{EntryOrigin, Library};
+use libreq::normalize::normalize_root;
+use libreq::playlist::{PlaylistItem, Queue};
+use libreq::provider::ProviderName;
+use libreq::race::RaceMessage;
+use libreq::recording::{recordings_dir, GhostProgress, RecordedKey, Recording};
+use libreq::response::{root_from_text, Root, SongStatus};
+use libreq::retry::Quota;
+use libreq::search::CandidateSource;
+use libreq::session::Session;
+use libreq::theme::Theme;
+use libreq::timed::TimedTest;
+
+// Saves the attempt of the day as it starts, true when this run is the one that counts.
+fn start_daily(daily: Daily, title: &str, artist: &str) -> Result<bool, String> {
+    let mut log = DailyLog::load(&daily_path())
+        .map_err(|e| format!("Failed to read the daily scores {}", e))?;
+    if !log.start(daily.day, title, artist) {
+        return Ok(false);
+    }
+
+    log.save(&daily_path())
+        .map(|_| true)
+        .map_err(|e| format!("Failed to save the daily attempt {}", e))
+}
+
+// Why a run on the daily does not count, when the day was already used up.
+pub fn daily_done_message(attempt: &DailyScore) -> String {
+    match attempt.finished {
+        true => format!(
+            "Daily already done today with {} points, this run does not count",
+            attempt.score
+        ),
+        false => {
+            String::from("Daily already started today and left unfinished, this run does not count")
+        }
+    }
+}
+
+// Scores the attempt of the day, or says why this run does not count.
+fn record_daily(daily: Daily, result: &SessionResult, counts: bool) -> String {
+    let mut log = match DailyLog::load(&daily_path()) {
+        Ok(log) => log,
+        Err(e) => return format!("Failed to read the daily scores {}", e),
+    };
+
+    if !counts || !log.finish(daily.day, result.wpm, result.accuracy) {
+        return match log.attempted(daily.day) {
+            Some(attempt) => daily_done_message(attempt),
+            None => String::from("This run of the daily does not count"),
+        };
+    }
+
+    match log.save(&daily_path()) {
+        Ok(()) => format!(
+            "Daily score {} | Streak {} days",
+            log.attempted(daily.day).map_or(0, |s| s.score),
+            log.streak(daily.day)
+        ),
+        Err(e) => format!("Failed to save the daily score {}", e),
+    }
+}
+
+// The history of the last seven days as a markdown report, csv and json, for sharing the week.
+fn export_week() -> Result<String, String> {
+    let now = unix_now();
+    let range = DateRange::last_days(7, now);
+    let results =
+        load_history(&history_path()).map_err(|e| format!("Failed to read the history {}", e))?;
+
+    let name = format!("lyricist-{}", format_date(now));
+    let written = [
+        ("md", ExportFormat::Markdown),
+        ("csv", ExportFormat::Csv),
+        ("json", ExportFormat::Json),
+    ]
+    .iter()
+    .try_for_each(|(ext, format)| {
+        let path = exports_dir().join(format!("{}.{}", name, ext));
+        export(&path, *format, &results, &range).map(|_| ())
+    });
+
+    match written {
+        Ok(()) => Ok(format!(
+            "The last 7 days were exported to {}",
+            exports_dir().display()
+        )),
+        Err(e) => Err(format!("Failed to export the results {}", e)),
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct TypingState {
+    pub advance_at: Option<Instant>,
+    pub config: Config,
     pub correct_hit: bool,
-    pub correct_hits: u32,
+    pub current_root: Option<(Root, Option<String>)>,
+    // The daily challenge, the first song started is cut down to the excerpt of the day.
+    pub daily: Option<Daily>,
+    pub daily_excerpt: Option<Root>,
+    // Set from the start of the run which uses up the day until its end.
+    pub daily_counts: bool,
+    // Score and streak of the daily, or why the run did not count.
+    pub daily_status: Option<String>,
+    // The part of every song to type, and what was cut out of the current one.
+    pub excerpt: Option<Excerpt>,
+    pub song_excerpt: Option<String>,
     pub keyboard_actions: Option<KeyboardActions>,
+    pub library: Library,
+    pub library_id: Option<String>,
+    pub library_view: Option<LibraryView>,
+    pub last_result: Option<SessionResult>,
+    pub personal_best: Option<SessionResult>,
+    pub picker: Option<Picker>,
+    pub queue: Option<Queue>,
+    pub queue_request: Option<PlaylistItem>,
+    // Provider quota as of the last lookup, shown in the search box.
+    pub quota: Option<Quota>,
+    pub race: Option<RaceState>,
+    pub recording: Option<Recording>,
+    // Replayed sessions are shown but never saved.
+    pub replaying: bool,
     pub search_request_build: Option<String>,
     pub error_string: Option<String>,
+    // Messages which are not failures, the error is shown over them.
+    pub status_string: Option<String>,
+    pub ghost: Option<Recording>,
+    // The recording raced with `--ghost`, kept over retries of its song.
+    pub chosen_ghost: Option<Recording>,
+    pub help: bool,
+    pub onboarding: Option<Onboarding>,
     pub search_completed: Option<String>,
-    pub song: Option<Song>,
-    pub total_hits: u32,
+    pub session: Option<Session>,
+    // Where the lyrics of the current song came from.
+    pub song_provider: Option<ProviderName>,
+    pub theme: Theme,
+    // The timed test in progress, its songs stream in until the clock ends it.
+    pub timed: Option<TimedTest>,
+    // Speed of the session clock, a replay at twice the speed runs it at 2.0.
+    pub time_scale: f64,
     pub update_text_color: bool,
     // Add error here, and render that error in the text box
 }
 
 impl TypingState {
-    pub fn get_sentence(&self) -> Option<&str> {
-        self.song.as_ref().map(|s| s.get_sentence_ref())
+    pub fn new(config: Config, library: Library) -> Self {
+        TypingState {
+            advance_at: None,
+            theme: config.theme.theme(),
+            config,
+            correct_hit: false,
+            current_root: None,
+            daily: None,
+            daily_excerpt: None,
+            daily_counts: false,
+            daily_status: None,
+            error_string: None,
+            status_string: None,
+            excerpt: None,
+            ghost: None,
+            chosen_ghost: None,
+            help: false,
+            keyboard_actions: None,
+            last_result: None,
+            library,
+            library_id: None,
+            library_view: None,
+            onboarding: None,
+            personal_best: None,
+            picker: None,
+            queue: None,
+            queue_request: None,
+            quota: None,
+            race: None,
+            recording: None,
+            replaying: false,
+            search_request_build: None,
+            search_completed: None,
+            session: None,
+            song_excerpt: None,
+            song_provider: None,
+            timed: None,
+            time_scale: 1.0,
+            update_text_color: false,
+        }
     }
 
-    pub fn get_current_cursor_location(&self) -> Option<u32> {
-        self.song.as_ref().map(|s| s.get_current_location())
+    // Seconds of typing so far on the session clock.
+    pub fn get_elapsed(&self) -> f64 {
+        self.session.as_ref().map_or(0.0, |s| s.elapsed())
     }
 
-    pub fn get_current_char(&self) -> Option<char> {
-        if let Some(s) = self.song.as_ref() {
-            return s.get_current_char();
-        }
+    pub fn get_sentence(&self) -> Option<&str> {
+        self.session.as_ref().map(|s| s.song.get_sentence_ref())
+    }
 
-        None
+    pub fn get_current_char(&self) -> Option<char> {
+        self.session
+            .as_ref()
+            .and_then(|s| s.song.get_current_char())
     }
 
     pub fn get_current_status(&self) -> Option<SongStatus> {
-        match self.song.as_ref() {
-            Some(s) => s.get_current_status(),
-            None => None,
-        }
+        self.session.as_ref().map(|s| s.status())
+    }
+
+    pub fn get_correct_hits(&self) -> u32 {
+        self.session.as_ref().map_or(0, |s| s.correct_hits)
+    }
+
+    pub fn get_total_hits(&self) -> u32 {
+        self.session.as_ref().map_or(0, |s| s.total_hits)
     }
 
     fn build_search_request(&mut self, c: char) {
@@ -50,20 +239,676 @@ impl TypingState {
         };
 
         s.push(c);
-        self.search_request_build = Some(s).take();
+        self.search_request_build = Some(s);
     }
 
     fn delete_chars_search_request(&mut self) {
-        if let Some(mut sr) = self.search_request_build.take().clone() {
+        if let Some(mut sr) = self.search_request_build.take() {
             sr.pop();
 
-            self.search_request_build = Some(sr).take();
+            self.search_request_build = Some(sr);
+        }
+    }
+
+    // Words per minute, a word being five correct hits.
+    pub fn get_wpm(&self) -> f64 {
+        self.session.as_ref().map_or(0.0, |s| s.wpm())
+    }
+
+    // Seconds left to finish the song at the current speed, none until there is a speed.
+    pub fn get_eta(&self) -> Option<f64> {
+        let (done, total) = self.session.as_ref()?.song.get_progress();
+        let chars_per_sec = self.get_wpm() * 5.0 / 60.0;
+        if chars_per_sec <= 0.0 {
+            return None;
+        }
+
+        Some(total.saturating_sub(done) as f64 / chars_per_sec)
+    }
+
+    // The recording of the best run on the song, if it was typed the same way as now.
+    fn find_ghost(&self, library_id: Option<&str>) -> Option<Recording> {
+        if self.config.ghost == GhostMode::Off || self.replaying || self.song_excerpt.is_some() {
+            return None;
+        }
+
+        let history = load_history(&history_path()).ok()?;
+        let best = personal_best(&history, library_id?, self.config.difficulty)?;
+        let recording = Recording::load(best.recording.as_ref()?).ok()?;
+
+        if recording.normalization == self.config.normalize {
+            Some(recording)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_ghost_progress(&self) -> Option<GhostProgress> {
+        self.ghost
+            .as_ref()
+            .map(|g| g.progress_at(self.get_elapsed()))
+    }
+
+    // Songs started by their library id come from the library.
+    pub fn start_song(&mut self, root: Root, library_id: Option<String>) {
+        let provider = library_id.as_ref().map(|_| ProviderName::Cache);
+        self.start_song_from(root, library_id, provider);
+    }
+
+    pub fn start_song_from(
+        &mut self,
+        root: Root,
+        library_id: Option<String>,
+        provider: Option<ProviderName>,
+    ) {
+        if root.is_empty() {
+            self.error_string = Some(String::from(
+                "The song has no lyrics, search for another one.",
+            ));
+            return;
+        }
+
+        // Normalization can leave nothing to type, a song like that would never finish.
+        let typable = normalize_root(&root, &self.config.normalize)
+            .iter()
+            .any(|l| !l.text.is_empty());
+        if !typable {
+            self.error_string = Some(String::from(
+                "The song has no lyrics, search for another one.",
+            ));
+            return;
+        }
+
+        match self.cut_excerpt(root, library_id.as_deref()) {
+            Ok(root) => self.begin_song(root, library_id, provider),
+            Err(e) => self.error_string = Some(e),
+        }
+    }
+
+    // The part of a new song to type, a replay already has the lines that were typed.
+    fn cut_excerpt(&mut self, root: Root, library_id: Option<&str>) -> Result<Root, String> {
+        if self.replaying {
+            self.song_excerpt = None;
+            return Ok(root);
+        }
+
+        if let Some(daily) = self.daily.filter(|_| self.daily_excerpt.is_none()) {
+            let (title, artist) = match library_id.and_then(|id| self.library.get(id)) {
+                Some(e) => (e.title.as_str(), e.artist.as_str()),
+                None => ("Unknown", "Unknown"),
+            };
+            self.daily_counts = start_daily(daily, title, artist)?;
+
+            let excerpt = daily.excerpt(&root);
+            self.daily_excerpt = Some(excerpt.to_owned());
+            self.song_excerpt = Some(String::from("daily"));
+            return Ok(excerpt);
+        }
+
+        let excerpt = match self.excerpt {
+            Some(e) => e,
+            None => {
+                self.song_excerpt = None;
+                return Ok(root);
+            }
+        };
+        let wpm = load_history(&history_path())
+            .ok()
+            .and_then(|h| recent_wpm(&h, self.config.difficulty, 10))
+            .unwrap_or(DEFAULT_WPM);
+        let root = excerpt.cut(&root, wpm)?;
+        self.song_excerpt = Some(excerpt.describe());
+        Ok(root)
+    }
+
+    // Starts the lines as they are, a retry does not cut them again.
+    fn begin_song(
+        &mut self,
+        root: Root,
+        library_id: Option<String>,
+        provider: Option<ProviderName>,
+    ) {
+        self.current_root = Some((root.to_owned(), library_id.to_owned()));
+        self.recording = match self.config.record && !self.replaying {
+            true => Some(Recording::new(
+                library_id.to_owned(),
+                root.to_owned(),
+                self.config.difficulty,
+                self.config.normalize.to_owned(),
+            )),
+            false => None,
+        };
+        if let Some(race) = self.race.as_mut().filter(|r| r.host) {
+            let (title, artist) = match library_id.as_ref().and_then(|id| self.library.get(id)) {
+                Some(e) => (e.title.to_owned(), e.artist.to_owned()),
+                None => (String::from("Unknown"), String::from("Unknown")),
+            };
+
+            race.last_progress = None;
+            race.outbox.push(RaceMessage::Start {
+                title,
+                artist,
+                lyrics: root.to_owned(),
+                difficulty: self.config.difficulty,
+                normalization: self.config.normalize.to_owned(),
+            });
+        }
+
+        self.ghost = match self.chosen_ghost.as_ref() {
+            Some(chosen) if chosen.lyrics == root => Some(chosen.to_owned()),
+            _ => self.find_ghost(library_id.as_deref()),
+        };
+        let mut session = Session::new(root, self.config.difficulty, &self.config.normalize);
+        session.time_scale = self.time_scale;
+        self.session = Some(session);
+        self.library_id = library_id;
+        self.song_provider = provider;
+        self.last_result = None;
+        self.personal_best = None;
+        self.error_string = None;
+        self.status_string = None;
+    }
+
+    // A new run of the timed test, on a new order of the library songs.
+    pub fn start_timed_test(&mut self) {
+        let timed = match self.timed.as_mut() {
+            Some(t) => t,
+            None => return,
+        };
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        *timed = TimedTest::new(timed.seconds, &self.library, seed);
+
+        let root = match timed.next_lines(&self.library) {
+            Some(root) => root,
+            None => {
+                self.error_string = Some(String::from(
+                    "A timed test types the songs of the library, search a song to add one.",
+                ));
+                return;
+            }
+        };
+        let seconds = timed.seconds;
+
+        self.song_excerpt = None;
+        self.begin_song(root, None, None);
+        // A replay of the keystrokes would not get the same songs streamed in.
+        self.recording = None;
+        if let Some(session) = self.session.as_mut() {
+            session.set_time_limit(seconds as f64);
+        }
+        self.stream_timed_lines();
+    }
+
+    // Songs searched for or picked from the library are still typed to their end.
+    fn in_timed_test(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|s| s.time_limit.is_some())
+    }
+
+    // Keeps a line ahead of the one being typed, so the test never runs out of lyrics.
+    fn stream_timed_lines(&mut self) {
+        let (timed, session) = match (self.timed.as_mut(), self.session.as_mut()) {
+            (Some(t), Some(s)) if s.time_limit.is_some() => (t, s),
+            _ => return,
+        };
+
+        let song = &session.song;
+        let ahead = song.song.as_ref().is_some_and(|lines| {
+            lines
+                .iter()
+                .skip(song.line_index as usize + 1)
+                .any(|l| !l.text.is_empty())
+        });
+        if ahead {
+            return;
+        }
+        if let Some(root) = timed.next_lines(&self.library) {
+            session.extend(&root);
+        }
+    }
+
+    // The end of a timed test comes from the clock instead of a keystroke.
+    fn check_clock(&mut self) {
+        let time_up = self.session.as_mut().is_some_and(|s| s.time_up());
+        if time_up && self.last_result.is_none() {
+            self.finish_song();
+        }
+    }
+
+    pub fn get_accuracy(&self) -> f64 {
+        self.session.as_ref().map_or(0.0, |s| s.accuracy())
+    }
+
+    fn finish_song(&mut self) {
+        let wpm = self.get_wpm();
+        let difficulty = self.config.difficulty;
+        let library_id = self.library_id.take();
+
+        let timed = self.timed.as_ref().filter(|_| self.in_timed_test());
+        let (title, artist) = match library_id.as_ref().and_then(|id| self.library.get(id)) {
+            Some(e) => (e.title.to_owned(), e.artist.to_owned()),
+            None if timed.is_some() => (
+                String::from("Timed test"),
+                format!("{} seconds", timed.map_or(0, |t| t.seconds)),
+            ),
+            None => (String::from("Unknown"), String::from("Unknown")),
+        };
+
+        let seconds = self.get_elapsed();
+        let loops = self.session.as_ref().map_or(0, |s| s.loops);
+        let mut result = SessionResult {
+            song_id: library_id.to_owned(),
+            title,
+            artist,
+            difficulty,
+            normalization: self.config.normalize.describe(),
+            wpm,
+            accuracy: self.get_accuracy(),
+            correct_hits: self.get_correct_hits(),
+            total_hits: self.get_total_hits(),
+            seconds,
+            finished_at: unix_now(),
+            recording: None,
+            provider: self.song_provider,
+            keys: self
+                .session
+                .as_ref()
+                .map(|s| s.stats().per_key())
+                .unwrap_or_default(),
+            excerpt: match (self.song_excerpt.to_owned(), loops) {
+                (excerpt, 0) => excerpt,
+                (Some(excerpt), n) => Some(format!("{}, {} line loops", excerpt, n)),
+                (None, n) => Some(format!("{} line loops", n)),
+            },
+        };
+
+        if self.replaying {
+            self.last_result = Some(result);
+            return;
+        }
+
+        if let Some(mut recording) = self.recording.take() {
+            recording.title = result.title.to_owned();
+            recording.artist = result.artist.to_owned();
+            match recording.save(&recordings_dir(), result.finished_at) {
+                Ok(path) => result.recording = Some(path),
+                Err(e) => self.error_string = Some(format!("Failed to save the recording {}", e)),
+            }
+        }
+
+        // The best is looked up before this run is added so the results can be compared to it.
+        self.personal_best = library_id.as_ref().and_then(|id| {
+            load_history(&history_path())
+                .ok()
+                .and_then(|h| personal_best(&h, id, difficulty).cloned())
+        });
+
+        let whole_song = result.excerpt.is_none();
+        if let Err(e) = append_result(&history_path(), &result) {
+            self.error_string = Some(format!("Failed to save the result {}", e));
+        }
+        // Other songs typed in daily mode, searched for or from the library, are not the daily.
+        let is_daily =
+            self.current_root.as_ref().map(|(root, _)| root) == self.daily_excerpt.as_ref();
+        self.daily_status = match self.daily {
+            Some(daily) if is_daily => Some(record_daily(daily, &result, self.daily_counts)),
+            _ => None,
+        };
+        // A retry of the daily is practice.
+        self.daily_counts = false;
+        self.last_result = Some(result);
+
+        // The best of a song in the library is over the whole of it.
+        if let Some(id) = library_id.filter(|_| whole_song) {
+            if let Err(e) = self.library.record_play(&id, wpm, difficulty) {
+                self.error_string = Some(format!("Failed to save the library {}", e));
+            }
+        }
+
+        let (correct_hits, total_hits) = (self.get_correct_hits(), self.get_total_hits());
+        if let Some(queue) = self.queue.as_mut() {
+            queue.stats.add_song(correct_hits, total_hits, seconds);
+            // n or Enter on the results skips the wait, r cancels it.
+            if queue.has_next() {
+                self.advance_at =
+                    Some(Instant::now() + Duration::from_secs(QUEUE_ADVANCE_DELAY_SECS));
+            }
+        }
+    }
+
+    // Asks the main loop to load the next song of the queue.
+    pub fn advance_queue(&mut self) {
+        self.advance_at = None;
+        if let Some(item) = self.queue.as_mut().and_then(|q| q.advance()) {
+            self.queue_request = Some(item);
+        }
+    }
+
+    // A song of the queue could not be loaded, show why and move on after a delay to read it.
+    pub fn skip_queue_item(&mut self, error: String) {
+        self.error_string = Some(error);
+        if let Some(queue) = self.queue.as_mut() {
+            queue.load_failed();
+            if !queue.is_finished() {
+                self.advance_at =
+                    Some(Instant::now() + Duration::from_secs(QUEUE_ADVANCE_DELAY_SECS));
+            }
+        }
+    }
+
+    pub fn process_race_message(&mut self, message: RaceMessage) {
+        let race = match self.race.as_mut() {
+            Some(r) => r,
+            None => return,
+        };
+
+        match message {
+            RaceMessage::Welcome { player_id, host } => {
+                race.player_id = Some(player_id);
+                race.host = host;
+            }
+            RaceMessage::Standings { players } => race.players = players,
+            // The host already started the song when it sent this.
+            RaceMessage::Start { .. } if race.host => (),
+            RaceMessage::Start {
+                lyrics,
+                difficulty,
+                normalization,
+                ..
+            } => {
+                race.last_progress = None;
+                self.picker = None;
+                self.library_view = None;
+                self.config.difficulty = difficulty;
+                self.config.normalize = normalization;
+                // The host already cut the part of the song to race on.
+                self.song_excerpt = None;
+                self.begin_song(lyrics, None, None);
+            }
+            RaceMessage::Join { .. } | RaceMessage::Progress { .. } => (),
+        }
+    }
+
+    // Queues a progress update for the race when the position moved since the last one.
+    pub fn update_race_progress(&mut self) {
+        let progress = match self.session.as_ref() {
+            Some(s) => s.song.get_progress(),
+            None => return,
+        };
+        let finished = self.get_current_status() == Some(SongStatus::Completed);
+        let wpm = match &self.last_result {
+            Some(r) if finished => r.wpm,
+            _ => self.get_wpm(),
+        };
+
+        if let Some(race) = self.race.as_mut() {
+            if race.last_progress == Some((progress.0, finished)) {
+                return;
+            }
+
+            race.last_progress = Some((progress.0, finished));
+            race.outbox.push(RaceMessage::Progress {
+                done: progress.0,
+                total: progress.1,
+                wpm,
+                finished,
+            });
+        }
+    }
+
+    // Called on every loop iteration, the keyboard task sends a NoPress at least every 600ms.
+    pub fn tick(&mut self) {
+        self.check_clock();
+        if self.advance_at.is_some_and(|a| Instant::now() >= a) {
+            self.advance_queue();
+        }
+    }
+
+    // Types the key into the session, and keeps it in the recording when there is one.
+    fn press_key(&mut self, key: RecordedKey) {
+        let keystroke = match self.session.as_mut().and_then(|s| s.press(key)) {
+            Some(k) => k,
+            None => return,
+        };
+
+        self.correct_hit = keystroke.correct;
+        self.update_text_color = key != RecordedKey::Backspace || keystroke.correct;
+        if let Some(recording) = self.recording.as_mut() {
+            recording.keys.push(keystroke);
+        }
+
+        if self.correct_hit && self.get_current_status() == Some(SongStatus::Completed) {
+            self.finish_song();
+        }
+        self.stream_timed_lines();
+    }
+
+    fn process_picker_events(&mut self, keyboard_actions: KeyboardActions) {
+        let action = match self.picker.as_mut() {
+            Some(p) => p.process_key(keyboard_actions.key_event.code),
+            None => return,
+        };
+
+        match action {
+            PickerAction::Choose(candidate) => {
+                self.picker = None;
+                match (&candidate.source, candidate.get_root()) {
+                    (CandidateSource::Provider(provider, _), Some(root)) => {
+                        // Without its library id the song would not be recorded, so it does not start.
+                        match self.library.add(
+                            &candidate.title,
+                            &candidate.artist,
+                            candidate.album.to_owned(),
+                            &root,
+                            EntryOrigin::Cache,
+                        ) {
+                            Ok(id) => self.start_song_from(root, Some(id), Some(*provider)),
+                            Err(e) => {
+                                self.error_string =
+                                    Some(format!("Failed to save the library {}", e))
+                            }
+                        }
+                    }
+                    (_, Some(root)) => {
+                        self.start_song(root, candidate.library_id().map(String::from))
+                    }
+                    (_, None) => {
+                        self.error_string =
+                            Some(format!("Could not load the lyrics of {}", candidate.title))
+                    }
+                }
+            }
+            PickerAction::Close => self.picker = None,
+            PickerAction::Nothing => (),
+        }
+
+        self.keyboard_actions = None;
+    }
+
+    fn process_library_events(&mut self, keyboard_actions: KeyboardActions) {
+        let action = match self.library_view.as_mut() {
+            Some(v) => v.process_key(
+                keyboard_actions.key_event.code,
+                &self.library,
+                self.config.difficulty,
+            ),
+            None => return,
+        };
+
+        let result = match action {
+            LibraryAction::Start(id) => {
+                self.library_view = None;
+                match self.library.load_root(&id) {
+                    Ok(root) => {
+                        self.start_song(root, Some(id));
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            LibraryAction::Delete(id) => self.library.remove(&id),
+            LibraryAction::ToggleFavorite(id) => self.library.toggle_favorite(&id),
+            LibraryAction::ToggleTag(id, tag) => self.library.toggle_tag(&id, &tag),
+            LibraryAction::Close => {
+                self.library_view = None;
+                Ok(())
+            }
+            LibraryAction::Nothing => Ok(()),
+        };
+
+        if let Err(e) = result {
+            self.error_string = Some(format!("Library error {}", e));
+        }
+
+        self.keyboard_actions = None;
+    }
+
+    fn process_results_events(&mut self, keyboard_actions: KeyboardActions) {
+        match keyboard_actions.key_event.code {
+            KeyCode::Char('r') if self.in_timed_test() => self.start_timed_test(),
+            KeyCode::Char('r') => {
+                self.advance_at = None;
+                if let Some((root, id)) = self.current_root.take() {
+                    self.begin_song(root, id, self.song_provider);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Enter => match self.queue {
+                Some(_) => self.advance_queue(),
+                None => self.library_view = Some(LibraryView::default()),
+            },
+            KeyCode::Char('s') => {
+                if let Some(result) = self.last_result.to_owned() {
+                    let report = SessionReport {
+                        result,
+                        stats: match self.session.as_ref() {
+                            Some(s) => s.stats().to_owned(),
+                            None => Default::default(),
+                        },
+                    };
+                    match save_report(&reports_dir(), &report) {
+                        Ok(path) => {
+                            self.status_string =
+                                Some(format!("Results saved to {}", path.display()))
+                        }
+                        Err(e) => {
+                            self.error_string = Some(format!("Failed to save the results {}", e))
+                        }
+                    };
+                }
+            }
+            KeyCode::Char('e') => match export_week() {
+                Ok(done) => self.status_string = Some(done),
+                Err(e) => self.error_string = Some(e),
+            },
+            KeyCode::Esc => {
+                self.advance_at = None;
+                self.session = None;
+            }
+            _ => (),
+        }
+
+        self.keyboard_actions = None;
+    }
+
+    fn process_onboarding_events(&mut self, keyboard_actions: KeyboardActions) {
+        let action = match self.onboarding.as_mut() {
+            Some(o) => o.process_key(keyboard_actions.key_event.code),
+            None => return,
+        };
+
+        match action {
+            OnboardingAction::UseOffline => self.config.offline = true,
+            OnboardingAction::StartLesson => {
+                self.onboarding = None;
+                self.start_song(sample_lesson(), None);
+            }
+            OnboardingAction::Skip => self.onboarding = None,
+            OnboardingAction::Nothing => (),
+        }
+
+        // Saving the config is what marks the first run as done.
+        if self.onboarding.is_none() {
+            if let Err(e) = self.config.save(&config_path()) {
+                self.error_string = Some(format!("Failed to save the config, Error {}", e));
+            }
+        }
+        self.keyboard_actions = None;
+    }
+
+    // A paste goes into the search box when it is open, and becomes a song between songs. It is
+    // ignored in the middle of a song, where it would type the lyrics for you.
+    fn process_paste(&mut self, text: &str) {
+        if let Some(search) = self.search_request_build.as_mut() {
+            search.push_str(&text.replace(['\r', '\n'], " "));
+            return;
+        }
+
+        let busy = self.onboarding.is_some()
+            || self.help
+            || self.picker.is_some()
+            || self.library_view.is_some()
+            || self.get_current_status() == Some(SongStatus::Continuing);
+        if busy {
+            return;
+        }
+
+        match root_from_text(text) {
+            root if root.is_empty() => {
+                self.error_string = Some(String::from("The pasted text has no lines to type."))
+            }
+            root => self.start_song_from(root, None, None),
         }
     }
 
     pub fn process_events_or_exit(&mut self, key_press_event: KeyboardEvent) -> bool {
+        // A key right after the clock ran out already belongs to the results.
+        self.check_clock();
         match key_press_event {
             KeyboardEvent::KeyPress(keyboard_actions) => {
+                if self.onboarding.is_some()
+                    && matches!(keyboard_actions.state, States::TYPE | States::EXIT)
+                {
+                    self.process_onboarding_events(keyboard_actions);
+                    return false;
+                }
+
+                // Any key closes the help.
+                if self.help
+                    && matches!(
+                        keyboard_actions.state,
+                        States::TYPE | States::EXIT | States::HELP
+                    )
+                {
+                    self.help = false;
+                    self.keyboard_actions = None;
+                    return false;
+                }
+
+                if self.picker.is_some()
+                    && matches!(keyboard_actions.state, States::TYPE | States::EXIT)
+                {
+                    self.process_picker_events(keyboard_actions);
+                    return false;
+                }
+
+                if self.library_view.is_some()
+                    && matches!(keyboard_actions.state, States::TYPE | States::EXIT)
+                {
+                    self.process_library_events(keyboard_actions);
+                    return false;
+                }
+
+                if self.get_current_status() == Some(SongStatus::Completed)
+                    && matches!(keyboard_actions.state, States::TYPE | States::EXIT)
+                {
+                    self.process_results_events(keyboard_actions);
+                    return false;
+                }
+
                 match keyboard_actions.state {
                     States::SEARCHOFF => {
                         self.search_completed = self.search_request_build.take();
@@ -72,7 +917,30 @@ impl TypingState {
                         let _ = self.search_request_build.take();
                     }
                     States::EXIT => return true,
-                    States::PAUSE => todo!(),
+                    States::LIBRARY => {
+                        self.library_view = match self.library_view {
+                            Some(_) => None,
+                            None => Some(LibraryView::default()),
+                        };
+                        self.keyboard_actions = None;
+                    }
+                    States::HELP => {
+                        self.help = true;
+                        self.keyboard_actions = None;
+                    }
+                    States::LOOP => {
+                        // The keystrokes of a looped line would not replay the same way.
+                        if let Some(session) = self.session.as_mut() {
+                            if session.toggle_loop().is_some() {
+                                self.recording = None;
+                            }
+                        }
+                        self.keyboard_actions = None;
+                    }
+                    States::PAUSE => {
+                        // There is no pause yet, the help lists it as not available.
+                        self.keyboard_actions = None;
+                    }
                     States::SEARCH => {
                         if keyboard_actions.key_event.modifiers != KeyModifiers::CONTROL {
                             if let KeyCode::Char(c) = keyboard_actions.key_event.code {
@@ -85,82 +953,44 @@ impl TypingState {
                         self.keyboard_actions = Some(keyboard_actions);
                     }
                     States::START => {
-                        // First start get a random song lyric.
+                        // First start get a random song lyric, there is no random song yet.
+                        self.keyboard_actions = None;
                     }
                     States::TYPE => {
-                        let c = match &self.get_current_char() {
-                            Some(c) => *c,
-                            None => return false,
+                        // Outside of a song `?` is free for the help.
+                        if self.get_current_char().is_none() {
+                            if keyboard_actions.key_event.code == KeyCode::Char('?') {
+                                self.help = true;
+                                self.keyboard_actions = None;
+                            }
+                            return false;
+                        }
+
+                        let typed = match keyboard_actions.key_event.code {
+                            KeyCode::Char(t) => Some(t),
+                            _ => None,
                         };
+                        self.press_key(match keyboard_actions.key_event.code {
+                            KeyCode::Char(t) => RecordedKey::Char(t),
+                            KeyCode::Backspace => RecordedKey::Backspace,
+                            _ => RecordedKey::Other,
+                        });
 
-                        self.total_hits += 1;
-                        if c.is_whitespace()
-                            && keyboard_actions
-                                .key_event
-                                .eq(&KeyEvent::from(KeyCode::Char(c)))
-                        {
-                            self.correct_hits += 1;
-                            self.correct_hit = true;
-                            self.update_text_color = true;
-
-                            self.song.as_mut().map(|s| s.update_sentence());
-
-                            self.keyboard_actions = Some(keyboard_actions);
-                        } else if c.is_uppercase()
-                            && keyboard_actions
-                                .key_event
-                                .eq(&KeyEvent::from(KeyCode::Char(c)))
-                        {
-                            self.correct_hits += 1;
-                            self.correct_hit = true;
-                            self.update_text_color = true;
-
-                            self.song.as_mut().map(|s| s.update_sentence());
-                            self.keyboard_actions = Some(keyboard_actions);
-                        }
-                        // if the c is lowercase and the keyevent happens to be a small one we have
-                        // to compare that.
-                        else if c.is_lowercase()
-                            && keyboard_actions
-                                .key_event
-                                .eq(&KeyEvent::from(KeyCode::Char(c)))
-                        {
-                            self.correct_hits += 1;
-                            self.correct_hit = true;
-                            self.update_text_color = true;
-
-                            self.song.as_mut().map(|s| s.update_sentence());
-
-                            let updated_keyboard_action =
-                                KeyboardActions::from_char(c.to_ascii_uppercase());
-                            self.keyboard_actions = Some(updated_keyboard_action);
-                        } else if KeyCode::Char(c).eq(&keyboard_actions.key_event.code) {
-                            self.correct_hits += 1;
-                            self.correct_hit = true;
-                            self.update_text_color = true;
-                            self.song.as_mut().map(|s| s.update_sentence());
-                            self.keyboard_actions = Some(keyboard_actions);
+                        // The keyboard only has the uppercase letters.
+                        if let Some(t) = typed {
+                            let key = if t.is_whitespace() {
+                                ' '
+                            } else {
+                                t.to_ascii_uppercase()
+                            };
+                            self.keyboard_actions = Some(KeyboardActions::from_char(key));
                         } else {
-                            if let KeyCode::Char(incorrect_typed_char) =
-                                keyboard_actions.key_event.code
-                            {
-                                if incorrect_typed_char.is_whitespace() {
-                                    let updated_keyboard_action = KeyboardActions::from_char(' ');
-                                    self.keyboard_actions = Some(updated_keyboard_action);
-                                } else {
-                                    let updated_keyboard_action = KeyboardActions::from_char(
-                                        incorrect_typed_char.to_ascii_uppercase(),
-                                    );
-                                    self.keyboard_actions = Some(updated_keyboard_action);
-                                }
-                            }
-
-                            self.correct_hit = false;
-                            self.update_text_color = true;
+                            self.keyboard_actions = None;
                         }
                     }
                 }
             }
+            KeyboardEvent::Paste(text) => self.process_paste(&text),
             KeyboardEvent::NoPress => {
                 self.correct_hit = false;
                 self.update_text_color = false;
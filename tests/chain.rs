@@ -0,0 +1,125 @@
+mod common;
+
+use common::start_mock_server;
+use libreq::library::{EntryOrigin, Library};
+use libreq::provider::{LocalFiles, ProviderChain, ProviderName};
+use libreq::response::Root2;
+use libreq::{Lyrics, Musixmatch};
+use reqwest::Client;
+use std::path::PathBuf;
+
+async fn musixmatch() -> Musixmatch {
+    let addr = start_mock_server().await;
+    Musixmatch::new(Client::new(), Some(format!("http://{}/songs/lyrics", addr)))
+}
+
+// The fixtures double as a lyrics directory, `paranoid.json` matches the title Paranoid.
+fn files() -> Option<LocalFiles> {
+    Some(LocalFiles {
+        dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"),
+    })
+}
+
+fn library(name: &str) -> Library {
+    let dir = std::env::temp_dir().join(format!("lyricist-chain-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    Library::empty(dir)
+}
+
+const ORDER: [ProviderName; 3] = [
+    ProviderName::Cache,
+    ProviderName::Files,
+    ProviderName::Musixmatch,
+];
+
+#[tokio::test]
+async fn the_first_provider_with_the_song_serves_it() {
+    let musixmatch = musixmatch().await;
+    let mut library = library("first");
+    let root = vec![Root2::line("From the library")];
+    library
+        .add("Paranoid", "Black Sabbath", None, &root, EntryOrigin::Cache)
+        .unwrap();
+
+    let chain = ProviderChain {
+        order: &ORDER,
+        offline: false,
+        library: &library,
+        files: files(),
+        musixmatch: &musixmatch,
+        lrclib: None,
+    };
+    let found = chain
+        .get_lyrics(String::from("t: Paranoid, a: Black Sabbath"))
+        .await
+        .unwrap();
+    assert_eq!(found.provider, ProviderName::Cache);
+    assert!(found.library_id.is_some());
+
+    let found = chain
+        .get_lyrics(String::from("t: Paranoid, a: Someone Else"))
+        .await
+        .unwrap();
+    assert_eq!((found.provider, found.root.len()), (ProviderName::Files, 3));
+
+    let found = chain
+        .get_lyrics(String::from("t: Flaky, a: Black Sabbath"))
+        .await
+        .unwrap();
+    assert_eq!(found.provider, ProviderName::Musixmatch);
+    assert_eq!(found.library_id, None);
+
+    std::fs::remove_dir_all(&library.dir).unwrap();
+}
+
+#[tokio::test]
+async fn offline_skips_the_online_providers() {
+    let musixmatch = musixmatch().await;
+    let library = library("offline");
+    let chain = ProviderChain {
+        order: &ORDER,
+        offline: true,
+        library: &library,
+        files: files(),
+        musixmatch: &musixmatch,
+        lrclib: None,
+    };
+
+    let e = chain
+        .get_lyrics(String::from("t: Flaky, a: Black Sabbath"))
+        .await
+        .unwrap_err();
+    assert!(e.starts_with("lyrics files: Flaky is not in"), "{}", e);
+    assert!(!e.contains("musixmatch"), "{}", e);
+}
+
+#[tokio::test]
+async fn every_failure_is_reported() {
+    let musixmatch = musixmatch().await;
+    let library = library("failures");
+    let chain = ProviderChain {
+        order: &[ProviderName::Files, ProviderName::Musixmatch],
+        offline: false,
+        library: &library,
+        files: files(),
+        musixmatch: &musixmatch,
+        lrclib: None,
+    };
+
+    let e = chain
+        .get_lyrics(String::from("t: Missing, a: Nobody"))
+        .await
+        .unwrap_err();
+    assert!(e.contains("lyrics files: Missing is not in"), "{}", e);
+    assert!(e.contains(" | musixmatch: The song was not found"), "{}", e);
+
+    let chain = ProviderChain {
+        order: &[],
+        ..chain
+    };
+    let e = chain
+        .get_lyrics(String::from("t: Missing, a: Nobody"))
+        .await
+        .unwrap_err();
+    assert_eq!(e, "No enabled lyrics provider has Missing");
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// What the mock answers, picked by the title of the query so one server covers every case, and by
+// how many times the title was asked for already.
+pub struct Fixture {
+    pub status: u16,
+    pub body: String,
+    pub headers: Vec<(&'static str, &'static str)>,
+    pub delay: Duration,
+}
+
+fn fixture_file(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+// The LRCLIB endpoints, `/api/get` has one track and `/api/search` a list of them.
+fn lrclib_fixture(path: &str, title: &str) -> (u16, &'static str) {
+    match (path, title) {
+        ("/api/get", "Paranoid") => (200, "lrclib_paranoid.json"),
+        ("/api/get", "Outage") => (503, "lrclib_not_found.json"),
+        ("/api/search", "Iron Man") => (200, "lrclib_search.json"),
+        ("/api/search", _) => (200, "lrclib_empty_search.json"),
+        _ => (404, "lrclib_not_found.json"),
+    }
+}
+
+pub fn fixture_for(path: &str, title: &str, seen: usize) -> Fixture {
+    if path.starts_with("/api/") {
+        let (status, file) = lrclib_fixture(path, title);
+        return Fixture {
+            status,
+            body: fixture_file(file),
+            headers: vec![],
+            delay: Duration::ZERO,
+        };
+    }
+
+    let quota = vec![
+        ("X-RateLimit-Requests-Limit", "500"),
+        ("X-RateLimit-Requests-Remaining", "498"),
+    ];
+    let (status, file, headers, delay) = match title {
+        "Paranoid" => (200, "paranoid.json", quota, 0),
+        "Flaky" if seen == 0 => (503, "unavailable.json", vec![], 0),
+        "Flaky" => (200, "paranoid.json", vec![], 0),
+        "Down" => (503, "unavailable.json", vec![], 0),
+        "Missing" => (404, "not_found.json", vec![], 0),
+        "Limited" => (429, "rate_limited.json", vec![("Retry-After", "1")], 0),
+        "Malformed" => (200, "malformed.json", vec![], 0),
+        "Instrumental" => (200, "empty.json", vec![], 0),
+        "Slow" => (200, "paranoid.json", vec![], 2000),
+        _ => (404, "not_found.json", vec![], 0),
+    };
+
+    Fixture {
+        status,
+        body: fixture_file(file),
+        headers,
+        delay: Duration::from_millis(delay),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+// The path and the title in the query string of the request line, `t` for musixmatch and
+// `track_name` for LRCLIB.
+fn requested_title(request: &str) -> (String, String) {
+    let target = request.split_whitespace().nth(1).unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let title = query
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| *k == "t" || *k == "track_name")
+        .map(|(_, v)| v.replace('+', " ").replace("%20", " "))
+        .unwrap_or_default();
+
+    (path.to_owned(), title)
+}
+
+async fn answer(mut stream: TcpStream, seen: Arc<Mutex<HashMap<String, usize>>>) {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let (path, title) = requested_title(&String::from_utf8_lossy(&request));
+    let count = {
+        let mut seen = seen.lock().unwrap();
+        let count = seen.entry(format!("{} {}", path, title)).or_default();
+        *count += 1;
+        *count - 1
+    };
+    let fixture = fixture_for(&path, &title, count);
+    tokio::time::sleep(fixture.delay).await;
+
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        fixture.status,
+        reason(fixture.status),
+        fixture.body.len()
+    );
+    for (name, value) in fixture.headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response.push_str("\r\n");
+    response.push_str(&fixture.body);
+
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// Serves the fixtures on a free localhost port until the test ends.
+pub async fn start_mock_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let seen = Arc::default();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(answer(stream, Arc::clone(&seen)));
+        }
+    });
+
+    addr
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::start_mock_server;
+use libreq::lrclib::Lrclib;
+use reqwest::Client;
+
+async fn lrclib() -> Lrclib {
+    let addr = start_mock_server().await;
+    Lrclib::new(Client::new(), Some(format!("http://{}/", addr)))
+}
+
+#[tokio::test]
+async fn synced_lyrics_keep_their_times() {
+    let root = lrclib()
+        .await
+        .fetch_root("t: Paranoid, a: Black Sabbath, al: Paranoid, d: 168")
+        .await
+        .unwrap();
+
+    let times: Vec<f64> = root.iter().map(|r| r.time.total).collect();
+    assert_eq!(times, vec![12.5, 15.1, 18.25]);
+    assert!(root[0].text.starts_with("Finished with my woman"));
+    assert_eq!(root[1].text, "");
+}
+
+#[tokio::test]
+async fn search_is_used_without_an_exact_match() {
+    let lrclib = lrclib().await;
+    assert_eq!(lrclib.search("t: Iron Man").await.unwrap().len(), 2);
+
+    // The instrumental result is passed over for the one with lyrics.
+    let root = lrclib
+        .fetch_root("t: Iron Man, a: Black Sabbath")
+        .await
+        .unwrap();
+    assert_eq!(root.len(), 2);
+    assert_eq!(root[0].text, "I am Iron Man");
+    assert_eq!(root[0].time.total, 0.0);
+}
+
+#[tokio::test]
+async fn missing_song() {
+    let e = lrclib()
+        .await
+        .fetch_root("t: Missing, a: Nobody")
+        .await
+        .unwrap_err();
+    assert!(e.contains("not found"), "{}", e);
+}
+
+#[tokio::test]
+async fn only_a_missing_track_falls_back_to_search() {
+    let e = lrclib()
+        .await
+        .fetch_root("t: Outage, a: Nobody")
+        .await
+        .unwrap_err();
+    assert_eq!(e, "LRCLIB answered 503 Service Unavailable");
+}
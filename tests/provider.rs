@@ -0,0 +1,179 @@
+mod common;
+
+use common::start_mock_server;
+use libreq::response::{Song, SongStatus};
+use libreq::retry::{Quota, RequestPolicy};
+use libreq::{generate_client, Musixmatch};
+
+// Retries without the long waits of the default policy.
+fn quick_policy() -> RequestPolicy {
+    RequestPolicy {
+        attempts: 3,
+        base_delay_ms: 10,
+        max_delay_ms: 200,
+        requests_per_minute: 0,
+        timeout_ms: 500,
+    }
+}
+
+// The client the app uses, with the timeout of the policy.
+async fn provider() -> Musixmatch {
+    let addr = start_mock_server().await;
+    let client = generate_client(None, &quick_policy()).unwrap();
+    Musixmatch::new(client, Some(format!("http://{}/songs/lyrics", addr)))
+        .with_policy(quick_policy())
+}
+
+#[tokio::test]
+async fn lyrics_become_a_song() {
+    let root = provider()
+        .await
+        .fetch_root("t: Paranoid, a: Black Sabbath")
+        .await
+        .unwrap();
+    assert_eq!(root.len(), 3);
+
+    let song = Song::new(root);
+    assert_eq!(song.get_current_status(), Some(SongStatus::Continuing));
+    assert!(song.sentence.starts_with("Finished with my woman"));
+}
+
+#[tokio::test]
+async fn free_text_queries_do_not_panic() {
+    let provider = provider().await;
+    let e = provider.fetch_root("paranoid").await.unwrap_err();
+    assert_eq!(
+        e,
+        "paranoid has no title, search with t: <title>, a: <artist>."
+    );
+
+    let root = provider.fetch_root("t: Paranoid").await.unwrap();
+    assert_eq!(root.len(), 3);
+}
+
+#[tokio::test]
+async fn missing_song() {
+    let e = provider()
+        .await
+        .fetch_root("t: Missing, a: Nobody")
+        .await
+        .unwrap_err();
+    assert!(e.contains("not found"), "{}", e);
+}
+
+#[tokio::test]
+async fn rate_limited() {
+    let e = provider()
+        .await
+        .fetch_root("t: Limited, a: Black Sabbath")
+        .await
+        .unwrap_err();
+    assert!(e.contains("Too many requests"), "{}", e);
+    // Retry-After asks for a second, longer than the policy is willing to wait.
+    assert!(e.ends_with("Retry in 1 seconds."), "{}", e);
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    let root = provider()
+        .await
+        .fetch_root("t: Flaky, a: Black Sabbath")
+        .await
+        .unwrap();
+    assert_eq!(root.len(), 3);
+}
+
+#[tokio::test]
+async fn retries_give_up_after_the_last_attempt() {
+    let e = provider()
+        .await
+        .fetch_root("t: Down, a: Black Sabbath")
+        .await
+        .unwrap_err();
+    assert!(e.contains("503"), "{}", e);
+}
+
+#[tokio::test]
+async fn quota_comes_from_the_headers() {
+    let provider = provider().await;
+    assert_eq!(provider.quota(), None);
+
+    provider
+        .fetch_root("t: Paranoid, a: Black Sabbath")
+        .await
+        .unwrap();
+    let quota = provider.quota().unwrap();
+    assert_eq!(
+        quota,
+        Quota {
+            remaining: 498,
+            limit: Some(500)
+        }
+    );
+    assert_eq!(quota.describe(), "498/500 requests left");
+}
+
+#[tokio::test]
+async fn request_budget_is_enforced() {
+    let provider = provider().await.with_policy(RequestPolicy {
+        requests_per_minute: 1,
+        ..quick_policy()
+    });
+
+    assert!(provider.fetch_root("t: Missing, a: Nobody").await.is_err());
+    let e = provider
+        .fetch_root("t: Missing, a: Nobody")
+        .await
+        .unwrap_err();
+    assert!(
+        e.starts_with("Request budget of 1 a minute used up"),
+        "{}",
+        e
+    );
+}
+
+#[tokio::test]
+async fn malformed_json() {
+    let e = provider()
+        .await
+        .fetch_root("t: Malformed, a: Black Sabbath")
+        .await
+        .unwrap_err();
+    assert!(e.starts_with("Failed to deserialize"), "{}", e);
+}
+
+#[tokio::test]
+async fn empty_lyrics() {
+    let e = provider()
+        .await
+        .fetch_root("t: Instrumental, a: Black Sabbath")
+        .await
+        .unwrap_err();
+    assert!(e.contains("no lyrics"), "{}", e);
+}
+
+#[tokio::test]
+async fn slow_response_times_out() {
+    let e = provider()
+        .await
+        .fetch_root("t: Slow, a: Black Sabbath")
+        .await
+        .unwrap_err();
+    assert!(e.starts_with("Could not get the song"), "{}", e);
+
+    // A policy without a timeout waits the slow response out.
+    let addr = start_mock_server().await;
+    let policy = RequestPolicy {
+        timeout_ms: 0,
+        ..quick_policy()
+    };
+    let provider = Musixmatch::new(
+        generate_client(None, &policy).unwrap(),
+        Some(format!("http://{}/songs/lyrics", addr)),
+    )
+    .with_policy(policy);
+    assert!(provider
+        .fetch_root("t: Slow, a: Black Sabbath")
+        .await
+        .is_ok());
+}